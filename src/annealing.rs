@@ -0,0 +1,387 @@
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyDict};
+
+use crate::cost::Minimize;
+#[cfg(feature = "python")]
+use crate::cotengra_tree_from_path;
+#[cfg(feature = "python")]
+use crate::network::TensorNetwork;
+
+/// The contraction tree that simulated annealing and parallel tempering start from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum InitialTree {
+    /// The tree found by cotengra's greedy optimizer.
+    #[default]
+    Greedy,
+    /// The best tree of cotengra's `random-greedy` preset.
+    RandomGreedy,
+    /// The tree found by cotengra's `kahypar` preset. Requires the `kahypar`
+    /// package.
+    Kahypar,
+    /// The tree of the given SSA path, e.g. from a previous run.
+    SsaPath(Vec<(usize, usize)>),
+    /// The tree of the given replace left path.
+    ReplacePath(Vec<(usize, usize)>),
+}
+
+#[cfg(feature = "python")]
+impl InitialTree {
+    /// Builds the Python `ContractionTree` of `network`.
+    pub(crate) fn build<'py>(
+        &self,
+        py: Python<'py>,
+        network: &TensorNetwork,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Greedy | Self::RandomGreedy | Self::Kahypar => {
+                let optimize = match self {
+                    Self::Greedy => "greedy",
+                    Self::RandomGreedy => "random-greedy",
+                    _ => "kahypar",
+                };
+                let args =
+                    (network.inputs(), network.outputs(), network.size_dict()).into_pyobject(py)?;
+                let kwargs = PyDict::new(py);
+                kwargs.set_item("optimize", optimize)?;
+                PyModule::import(py, "cotengra")?
+                    .getattr("array_contract_tree")?
+                    .call(args, Some(&kwargs))
+            }
+            Self::SsaPath(path) => cotengra_tree_from_path(py, network, path.clone(), true),
+            Self::ReplacePath(path) => cotengra_tree_from_path(py, network, path.clone(), false),
+        }
+    }
+}
+
+/// How simulated annealing treats sliced legs when a `target_size` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceMode {
+    /// Slices the tree once before annealing.
+    Basic,
+    /// Reslices the tree after every step.
+    Reslice,
+    /// Lets the sliced legs drift during annealing.
+    Drift,
+}
+
+impl SliceMode {
+    /// The corresponding string argument of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Basic => "basic",
+            Self::Reslice => "reslice",
+            Self::Drift => "drift",
+        }
+    }
+}
+
+/// The options for simulated annealing, corresponding to cotengra's
+/// `simulated_anneal_tree`.
+///
+/// Unassigned options will not be passed to cotengra and hence the Python default
+/// values will be used. Please see the cotengra documentation for details on the
+/// parameters.
+#[derive(Debug, Clone, Default)]
+pub struct AnnealingOptions {
+    tsteps: Option<usize>,
+    numiter: Option<usize>,
+    tfinal: Option<f64>,
+    tstart: Option<f64>,
+    minimize: Option<Minimize>,
+    target_size: Option<u64>,
+    slice_mode: Option<SliceMode>,
+    seed: Option<u64>,
+    progbar: Option<bool>,
+}
+
+impl AnnealingOptions {
+    /// Creates the default annealing options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `tsteps` argument, the number of temperature steps.
+    pub fn with_tsteps(mut self, tsteps: usize) -> Self {
+        self.tsteps = Some(tsteps);
+        self
+    }
+
+    /// Sets the `numiter` argument, the number of sweeps per temperature step.
+    pub fn with_numiter(mut self, numiter: usize) -> Self {
+        self.numiter = Some(numiter);
+        self
+    }
+
+    /// Sets the `tfinal` argument, the final temperature.
+    pub fn with_tfinal(mut self, tfinal: f64) -> Self {
+        self.tfinal = Some(tfinal);
+        self
+    }
+
+    /// Sets the `tstart` argument, the initial temperature.
+    pub fn with_tstart(mut self, tstart: f64) -> Self {
+        self.tstart = Some(tstart);
+        self
+    }
+
+    /// Sets the `minimize` argument.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `target_size` argument, the maximum size of any intermediate tensor
+    /// which is enforced by slicing.
+    pub fn with_target_size(mut self, target_size: u64) -> Self {
+        self.target_size = Some(target_size);
+        self
+    }
+
+    /// Sets the `slice_mode` argument.
+    pub fn with_slice_mode(mut self, slice_mode: SliceMode) -> Self {
+        self.slice_mode = Some(slice_mode);
+        self
+    }
+
+    /// Sets the `seed` argument.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the `progbar` argument.
+    pub fn with_progbar(mut self, progbar: bool) -> Self {
+        self.progbar = Some(progbar);
+        self
+    }
+}
+
+#[cfg(feature = "python")]
+impl AnnealingOptions {
+    /// The seed, if assigned.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        if let Some(tsteps) = self.tsteps {
+            kwargs.set_item("tsteps", tsteps)?;
+        }
+        if let Some(numiter) = self.numiter {
+            kwargs.set_item("numiter", numiter)?;
+        }
+        if let Some(tfinal) = self.tfinal {
+            kwargs.set_item("tfinal", tfinal)?;
+        }
+        if let Some(tstart) = self.tstart {
+            kwargs.set_item("tstart", tstart)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(target_size) = self.target_size {
+            kwargs.set_item("target_size", target_size)?;
+        }
+        if let Some(slice_mode) = self.slice_mode {
+            kwargs.set_item("slice_mode", slice_mode.as_cotengra_str())?;
+        }
+        if let Some(seed) = self.seed {
+            kwargs.set_item("seed", seed)?;
+        }
+        if let Some(progbar) = self.progbar {
+            kwargs.set_item("progbar", progbar)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+/// The options for parallel tempering, corresponding to cotengra's
+/// `parallel_temper_tree`.
+///
+/// Unassigned options will not be passed to cotengra and hence the Python default
+/// values will be used. Please see the cotengra documentation for details on the
+/// parameters.
+#[derive(Debug, Clone, Default)]
+pub struct TemperingOptions {
+    tsteps: Option<usize>,
+    numiter: Option<usize>,
+    tfinal: Option<f64>,
+    tstart: Option<f64>,
+    num_replicas: Option<usize>,
+    swap_rate: Option<f64>,
+    coeff_t: Option<f64>,
+    minimize: Option<Minimize>,
+    target_size: Option<u64>,
+    parallel_slot: Option<bool>,
+    seed: Option<u64>,
+    progbar: Option<bool>,
+}
+
+impl TemperingOptions {
+    /// Creates the default tempering options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `tsteps` argument, the number of temperature steps.
+    pub fn with_tsteps(mut self, tsteps: usize) -> Self {
+        self.tsteps = Some(tsteps);
+        self
+    }
+
+    /// Sets the `numiter` argument, the number of sweeps between swaps.
+    pub fn with_numiter(mut self, numiter: usize) -> Self {
+        self.numiter = Some(numiter);
+        self
+    }
+
+    /// Sets the `tfinal` argument, the lowest temperature of the replicas.
+    pub fn with_tfinal(mut self, tfinal: f64) -> Self {
+        self.tfinal = Some(tfinal);
+        self
+    }
+
+    /// Sets the `tstart` argument, the highest temperature of the replicas.
+    pub fn with_tstart(mut self, tstart: f64) -> Self {
+        self.tstart = Some(tstart);
+        self
+    }
+
+    /// Sets the `num_replicas` argument, the number of trees annealed at different
+    /// temperatures.
+    pub fn with_num_replicas(mut self, num_replicas: usize) -> Self {
+        self.num_replicas = Some(num_replicas);
+        self
+    }
+
+    /// Sets the `swap_rate` argument, how often replicas of neighboring
+    /// temperatures are swapped.
+    pub fn with_swap_rate(mut self, swap_rate: f64) -> Self {
+        self.swap_rate = Some(swap_rate);
+        self
+    }
+
+    /// Sets the `coeff_t` argument, which scales the temperatures in the swap
+    /// acceptance.
+    pub fn with_coeff_t(mut self, coeff_t: f64) -> Self {
+        self.coeff_t = Some(coeff_t);
+        self
+    }
+
+    /// Sets the `minimize` argument.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `target_size` argument, the maximum size of any intermediate tensor
+    /// which is enforced by slicing.
+    pub fn with_target_size(mut self, target_size: u64) -> Self {
+        self.target_size = Some(target_size);
+        self
+    }
+
+    /// Sets the `parallel_slot` argument.
+    pub fn with_parallel_slot(mut self, parallel_slot: bool) -> Self {
+        self.parallel_slot = Some(parallel_slot);
+        self
+    }
+
+    /// Sets the `seed` argument.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the `progbar` argument.
+    pub fn with_progbar(mut self, progbar: bool) -> Self {
+        self.progbar = Some(progbar);
+        self
+    }
+}
+
+#[cfg(feature = "python")]
+impl TemperingOptions {
+    /// The seed, if assigned.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        if let Some(tsteps) = self.tsteps {
+            kwargs.set_item("tsteps", tsteps)?;
+        }
+        if let Some(numiter) = self.numiter {
+            kwargs.set_item("numiter", numiter)?;
+        }
+        if let Some(tfinal) = self.tfinal {
+            kwargs.set_item("tfinal", tfinal)?;
+        }
+        if let Some(tstart) = self.tstart {
+            kwargs.set_item("tstart", tstart)?;
+        }
+        if let Some(num_replicas) = self.num_replicas {
+            kwargs.set_item("num_replicas", num_replicas)?;
+        }
+        if let Some(swap_rate) = self.swap_rate {
+            kwargs.set_item("swap_rate", swap_rate)?;
+        }
+        if let Some(coeff_t) = self.coeff_t {
+            kwargs.set_item("coeff_t", coeff_t)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(target_size) = self.target_size {
+            kwargs.set_item("target_size", target_size)?;
+        }
+        if let Some(parallel_slot) = self.parallel_slot {
+            kwargs.set_item("parallel_slot", parallel_slot)?;
+        }
+        if let Some(seed) = self.seed {
+            kwargs.set_item("seed", seed)?;
+        }
+        if let Some(progbar) = self.progbar {
+            kwargs.set_item("progbar", progbar)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+#[cfg(all(test, feature = "python"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kwargs_without_seed() {
+        Python::initialize();
+        Python::attach(|py| {
+            let options = AnnealingOptions::new().with_tsteps(3).with_tfinal(0.01);
+            let kwargs = options.to_kwargs(py).unwrap();
+            assert_eq!(kwargs.len(), 2);
+            let tsteps: usize = kwargs
+                .get_item("tsteps")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(tsteps, 3);
+            assert!(kwargs.get_item("seed").unwrap().is_none());
+
+            let options = TemperingOptions::new().with_numiter(7).with_num_replicas(4);
+            let kwargs = options.to_kwargs(py).unwrap();
+            assert_eq!(kwargs.len(), 2);
+            let numiter: usize = kwargs
+                .get_item("numiter")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(numiter, 7);
+        });
+    }
+}
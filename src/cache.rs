@@ -0,0 +1,499 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::canonical::CanonicalForm;
+use crate::cost::{contraction_cost, ContractionCost};
+use crate::network::TensorNetwork;
+use crate::optimizer::{ContractionPath, Optimizer};
+use crate::utils::validate_ssa_path;
+use crate::Result;
+
+/// Reasons why the on-disk [`PathCache`] could not be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    /// Reading or writing a cache file failed.
+    Io { path: PathBuf, message: String },
+    /// A cache file could not be parsed.
+    Malformed { path: PathBuf, line: usize },
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, message } => write!(f, "cannot access {}: {message}", path.display()),
+            Self::Malformed { path, line } => {
+                write!(f, "malformed line {line} in {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// A contraction path stored in a [`PathCache`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedPath {
+    /// The contraction path in SSA format, in the tensor order of the queried
+    /// network.
+    pub ssa_path: ContractionPath,
+    /// The cost of the path.
+    pub cost: ContractionCost,
+}
+
+/// A cache entry: the certificate of the network and its best path in canonical
+/// tensor order.
+struct Entry {
+    certificate: String,
+    cost: ContractionCost,
+    ssa_path: ContractionPath,
+}
+
+/// A persistent cache of the best contraction path found for each network.
+///
+/// Networks are identified up to the names of their legs and the order of their
+/// tensors, so a path found for one network is reused for every isomorphic one and
+/// remapped to its tensor order. Each network hash is stored in a text file in the
+/// cache directory, which can be shared between processes.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::cache::PathCache;
+/// # use rustengra::network::TensorNetwork;
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2]],
+///     &[0],
+///     &FxHashMap::from_iter([(0, 2), (1, 10), (2, 3)]),
+/// )
+/// .unwrap();
+/// let permuted = TensorNetwork::from_usize_legs(
+///     &[vec![5], vec![7, 6], vec![6, 5]],
+///     &[7],
+///     &FxHashMap::from_iter([(5, 3), (6, 10), (7, 2)]),
+/// )
+/// .unwrap();
+///
+/// # let directory = std::env::temp_dir().join(format!("rustengra-doc-{}", std::process::id()));
+/// let cache = PathCache::new(&directory);
+/// cache.update(&network, &[(1, 2), (0, 3)]).unwrap();
+/// let cached = cache.get(&permuted).unwrap().unwrap();
+/// assert_eq!(cached.ssa_path, vec![(2, 0), (1, 3)]);
+/// # std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathCache {
+    directory: PathBuf,
+}
+
+impl PathCache {
+    /// Creates a cache in `directory`, which is created on the first update.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// The directory of the cache files.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Looks up the best path stored for `network` or any network isomorphic to it.
+    pub fn get(&self, network: &TensorNetwork) -> Result<Option<CachedPath>> {
+        let canonical = CanonicalForm::new(network);
+        let entries = self.read(&self.file(&canonical))?;
+        Ok(entries
+            .into_iter()
+            .find(|entry| entry.certificate == canonical.certificate())
+            .map(|entry| CachedPath {
+                ssa_path: canonical.path_from_canonical(&entry.ssa_path),
+                cost: entry.cost,
+            }))
+    }
+
+    /// Stores `ssa_path` for `network` if no path is stored yet or if it has fewer
+    /// flops than the stored one, with the write as tie breaker. Returns whether the
+    /// path was stored.
+    ///
+    /// Fails if the path is not a valid SSA path of `network`.
+    pub fn update(&self, network: &TensorNetwork, ssa_path: &[(usize, usize)]) -> Result<bool> {
+        validate_ssa_path(ssa_path, network)?;
+        let cost = contraction_cost(network, ssa_path);
+
+        let canonical = CanonicalForm::new(network);
+        let entry = Entry {
+            certificate: canonical.certificate().to_owned(),
+            cost,
+            ssa_path: canonical.path_to_canonical(ssa_path),
+        };
+        self.modify(&self.file(&canonical), |entries| {
+            match entries
+                .iter_mut()
+                .find(|other| other.certificate == entry.certificate)
+            {
+                Some(other) if !is_better(&cost, &other.cost) => return false,
+                Some(other) => *other = entry,
+                None => entries.push(entry),
+            }
+            true
+        })
+    }
+
+    /// Stores `ssa_path` like [`PathCache::update`]. Returns the stored path if it is
+    /// better than `ssa_path`.
+    pub fn improve(
+        &self,
+        network: &TensorNetwork,
+        ssa_path: &[(usize, usize)],
+    ) -> Result<Option<CachedPath>> {
+        if self.update(network, ssa_path)? {
+            return Ok(None);
+        }
+        let cost = contraction_cost(network, ssa_path);
+        Ok(self
+            .get(network)?
+            .filter(|cached| is_better(&cached.cost, &cost)))
+    }
+
+    fn file(&self, canonical: &CanonicalForm) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.txt", canonical.hash()))
+    }
+
+    /// Reads the entries of `file`, lets `change` modify them and writes them back
+    /// if it returns true. Networks with the same hash share a file, so concurrent
+    /// writers are serialized by an exclusive lock on a `.lock` file next to it.
+    fn modify(&self, file: &Path, change: impl FnOnce(&mut Vec<Entry>) -> bool) -> Result<bool> {
+        fs::create_dir_all(&self.directory).map_err(|err| io_error(&self.directory, &err))?;
+        let lock_file = file.with_extension("lock");
+        let lock = File::create(&lock_file).map_err(|err| io_error(&lock_file, &err))?;
+        lock.lock().map_err(|err| io_error(&lock_file, &err))?;
+
+        let mut entries = self.read(file)?;
+        let changed = change(&mut entries);
+        if changed {
+            self.write(file, &entries)?;
+        }
+        // The lock is released when `lock` is dropped
+        Ok(changed)
+    }
+
+    fn read(&self, file: &Path) -> Result<Vec<Entry>> {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(io_error(file, &err).into()),
+        };
+
+        let malformed = |line: usize| CacheError::Malformed {
+            path: file.to_owned(),
+            line,
+        };
+        let lines = content.lines().collect::<Vec<_>>();
+        if lines.len() % 3 != 0 {
+            return Err(malformed(lines.len()).into());
+        }
+        lines
+            .chunks(3)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let line = 3 * index + 1;
+                let certificate = chunk[0]
+                    .strip_prefix("certificate ")
+                    .ok_or_else(|| malformed(line))?;
+                let cost = chunk[1]
+                    .strip_prefix("cost ")
+                    .and_then(parse_cost)
+                    .ok_or_else(|| malformed(line + 1))?;
+                let ssa_path = chunk[2]
+                    .strip_prefix("path")
+                    .and_then(parse_path)
+                    .ok_or_else(|| malformed(line + 2))?;
+                Ok(Entry {
+                    certificate: certificate.to_owned(),
+                    cost,
+                    ssa_path,
+                })
+            })
+            .collect()
+    }
+
+    fn write(&self, file: &Path, entries: &[Entry]) -> Result<()> {
+        let mut content = String::new();
+        for entry in entries {
+            let cost = entry.cost;
+            content.push_str(&format!("certificate {}\n", entry.certificate));
+            content.push_str(&format!(
+                "cost {} {} {} {}\n",
+                cost.flops, cost.write, cost.max_size, cost.peak_memory
+            ));
+            content.push_str("path");
+            for (i, j) in &entry.ssa_path {
+                content.push_str(&format!(" {i},{j}"));
+            }
+            content.push('\n');
+        }
+
+        // Write to a temporary file first, so concurrent readers never see a
+        // partially written file
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let temporary = file.with_extension(format!(
+            "tmp{}-{}",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temporary, content).map_err(|err| io_error(&temporary, &err))?;
+        fs::rename(&temporary, file).map_err(|err| io_error(file, &err))?;
+        Ok(())
+    }
+}
+
+fn io_error(path: &Path, err: &std::io::Error) -> CacheError {
+    CacheError::Io {
+        path: path.to_owned(),
+        message: err.to_string(),
+    }
+}
+
+fn parse_cost(line: &str) -> Option<ContractionCost> {
+    let values = line
+        .split_whitespace()
+        .map(|value| value.parse().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    let [flops, write, max_size, peak_memory] = values[..] else {
+        return None;
+    };
+    Some(ContractionCost {
+        flops,
+        write,
+        max_size,
+        peak_memory,
+    })
+}
+
+fn parse_path(line: &str) -> Option<ContractionPath> {
+    line.split_whitespace()
+        .map(|pair| {
+            let (i, j) = pair.split_once(',')?;
+            Some((i.parse().ok()?, j.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Whether `cost` has fewer flops than `other`, with the write as tie breaker.
+fn is_better(cost: &ContractionCost, other: &ContractionCost) -> bool {
+    cost.flops < other.flops || (cost.flops == other.flops && cost.write < other.write)
+}
+
+/// Wraps an [`Optimizer`] with a [`PathCache`], see [`Optimizer::cached`].
+///
+/// Returns the cached path if there is one. Otherwise runs the optimizer and
+/// stores its path. If `refine` is set, the optimizer also runs on a cache hit and
+/// the better of both paths is stored and returned. Failures of the cache are
+/// ignored, so the optimizer's path is returned even if the cache cannot be read
+/// or written.
+#[derive(Debug, Clone)]
+pub struct CachedOptimizer<O> {
+    pub optimizer: O,
+    pub cache: PathCache,
+    pub refine: bool,
+}
+
+impl<O: Optimizer> Optimizer for CachedOptimizer<O> {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        let cached = self.cache.get(network).ok().flatten();
+        if let Some(cached) = &cached {
+            if !self.refine {
+                return Ok(cached.ssa_path.clone());
+            }
+        }
+
+        let path = self.optimizer.optimize(network)?;
+        match self.cache.improve(network, &path) {
+            Ok(Some(cached)) => Ok(cached.ssa_path),
+            Ok(None) | Err(_) => Ok(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+    use crate::optimizer::NativeGreedy;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("rustengra-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn network() -> TensorNetwork {
+        TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0], vec![4, 0]],
+            &[4],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_keeps_best_path() {
+        let directory = directory("cache-best");
+        let cache = PathCache::new(&directory);
+        let network = network();
+        assert_eq!(cache.get(&network), Ok(None));
+
+        let worse = [(0, 2), (5, 1), (6, 3), (7, 4)];
+        let better = [(0, 1), (2, 3), (5, 6), (7, 4)];
+        assert!(
+            contraction_cost(&network, &better).flops < contraction_cost(&network, &worse).flops
+        );
+
+        assert_eq!(cache.update(&network, &worse), Ok(true));
+        assert_eq!(cache.update(&network, &better), Ok(true));
+        assert_eq!(cache.update(&network, &worse), Ok(false));
+        let cached = cache.get(&network).unwrap().unwrap();
+        assert_eq!(cached.ssa_path, better);
+        assert_eq!(cached.cost, contraction_cost(&network, &better));
+
+        assert!(cache.update(&network, &[(0, 0)]).is_err());
+
+        assert_eq!(cache.improve(&network, &better), Ok(None));
+        let improved = cache.improve(&network, &worse).unwrap().unwrap();
+        assert_eq!(improved.ssa_path, better);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_malformed_file() {
+        let directory = directory("cache-malformed");
+        let cache = PathCache::new(&directory);
+        let network = network();
+        let file = cache.file(&CanonicalForm::new(&network));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(&file, "certificate x\ncost 1 2\npath 0,1\n").unwrap();
+
+        let err = cache.get(&network).unwrap_err();
+        assert_eq!(
+            err,
+            CacheError::Malformed {
+                path: file,
+                line: 2
+            }
+            .into()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_cached_optimizer() {
+        let directory = directory("cache-optimizer");
+        let network = network();
+        let optimizer = NativeGreedy::default().cached(PathCache::new(&directory), false);
+
+        let path = optimizer.optimize(&network).unwrap();
+        assert_eq!(validate_ssa_path(&path, &network), Ok(()));
+        assert_eq!(
+            optimizer.cache.get(&network).unwrap().unwrap().ssa_path,
+            path
+        );
+        assert_eq!(optimizer.optimize(&network).unwrap(), path);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writes() {
+        let directory = directory("cache-concurrent");
+        let cache = PathCache::new(&directory);
+        let network = network();
+        let path = [(0, 1), (2, 3), (5, 6), (7, 4)];
+        cache.update(&network, &path).unwrap();
+        let file = cache.file(&CanonicalForm::new(&network));
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let entries = cache.read(&file).unwrap();
+                        cache.write(&file, &entries).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.get(&network).unwrap().unwrap().ssa_path, path);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let directory = directory("cache-updates");
+        let cache = PathCache::new(&directory);
+        let network = network();
+        let worse = [(0, 2), (5, 1), (6, 3), (7, 4)];
+        let better = [(0, 1), (2, 3), (5, 6), (7, 4)];
+        let file = cache.file(&CanonicalForm::new(&network));
+        // Each thread stores entries of its own certificates in the same file, while
+        // also updating the path of `network`
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let cache = &cache;
+                let network = &network;
+                let file = &file;
+                scope.spawn(move || {
+                    for i in 0..20 {
+                        let path = if (thread + i) % 2 == 0 {
+                            &worse
+                        } else {
+                            &better
+                        };
+                        cache.update(network, path).unwrap();
+                        cache
+                            .modify(file, |entries| {
+                                entries.push(Entry {
+                                    certificate: format!("{thread}-{i}"),
+                                    cost: contraction_cost(network, &worse),
+                                    ssa_path: worse.to_vec(),
+                                });
+                                true
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let entries = cache.read(&file).unwrap();
+        assert_eq!(entries.len(), 8 * 20 + 1);
+        for thread in 0..8 {
+            for i in 0..20 {
+                let certificate = format!("{thread}-{i}");
+                assert!(entries.iter().any(|entry| entry.certificate == certificate));
+            }
+        }
+        assert_eq!(cache.get(&network).unwrap().unwrap().ssa_path, better);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_cache_failures_are_ignored() {
+        // The cache directory cannot be created below a file
+        let file = directory("cache-file");
+        fs::write(&file, "").unwrap();
+        let network = network();
+        let cache = PathCache::new(file.join("cache"));
+        assert!(matches!(
+            cache.update(&network, &[(0, 1), (2, 3), (5, 6), (7, 4)]),
+            Err(crate::Error::Cache(CacheError::Io { .. }))
+        ));
+
+        let optimizer = NativeGreedy::default().cached(cache, true);
+        let path = optimizer.optimize(&network).unwrap();
+        assert_eq!(validate_ssa_path(&path, &network), Ok(()));
+        fs::remove_file(&file).unwrap();
+    }
+}
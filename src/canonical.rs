@@ -0,0 +1,499 @@
+use rustc_hash::FxHashMap;
+
+use crate::network::{NetworkError, TensorNetwork};
+
+/// Hashes `values` with 64 bit FNV-1a. Unlike the std hashers, the result is
+/// stable across platforms, processes and versions.
+fn fnv1a(values: impl IntoIterator<Item = u64>) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = OFFSET;
+    for value in values {
+        for byte in value.to_le_bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Marks a color as individualized.
+const INDIVIDUALIZED: u64 = u64::MAX;
+
+/// A tensor network as a bipartite graph of tensors and legs, with edges weighted
+/// by the number of times a leg appears on a tensor.
+struct Graph {
+    leg_names: Vec<String>,
+    tensor_legs: Vec<Vec<(usize, u64)>>,
+    leg_tensors: Vec<Vec<(usize, u64)>>,
+}
+
+impl Graph {
+    fn new(network: &TensorNetwork) -> Self {
+        let mut ids = FxHashMap::default();
+        let mut leg_names = Vec::new();
+        let mut leg_tensors = Vec::<Vec<(usize, u64)>>::new();
+        let mut tensor_legs = Vec::with_capacity(network.num_tensors());
+        for (tensor, legs) in network.inputs().iter().enumerate() {
+            let mut edges = Vec::<(usize, u64)>::with_capacity(legs.len());
+            for leg in legs {
+                let id = *ids.entry(leg).or_insert_with(|| {
+                    leg_names.push(leg.clone());
+                    leg_tensors.push(Vec::new());
+                    leg_names.len() - 1
+                });
+                match edges.iter_mut().find(|(other, _)| *other == id) {
+                    Some((_, count)) => *count += 1,
+                    None => edges.push((id, 1)),
+                }
+            }
+            for (leg, count) in &edges {
+                leg_tensors[*leg].push((tensor, *count));
+            }
+            tensor_legs.push(edges);
+        }
+        Self {
+            leg_names,
+            tensor_legs,
+            leg_tensors,
+        }
+    }
+
+    /// Refines the colors by the colors of the neighbors until the partition into
+    /// colors is stable.
+    fn refine(&self, tensor_colors: &mut Vec<u64>, leg_colors: &mut Vec<u64>) {
+        let neighborhood = |color: u64, edges: &[(usize, u64)], colors: &[u64]| {
+            let mut neighbors = edges
+                .iter()
+                .map(|(node, count)| (colors[*node], *count))
+                .collect::<Vec<_>>();
+            neighbors.sort_unstable();
+            fnv1a(
+                [color, neighbors.len() as u64].into_iter().chain(
+                    neighbors
+                        .into_iter()
+                        .flat_map(|(color, count)| [color, count]),
+                ),
+            )
+        };
+
+        let mut num_colors = count_distinct(tensor_colors) + count_distinct(leg_colors);
+        loop {
+            let new_tensor_colors = (0..tensor_colors.len())
+                .map(|tensor| {
+                    neighborhood(tensor_colors[tensor], &self.tensor_legs[tensor], leg_colors)
+                })
+                .collect::<Vec<_>>();
+            let new_leg_colors = (0..leg_colors.len())
+                .map(|leg| neighborhood(leg_colors[leg], &self.leg_tensors[leg], tensor_colors))
+                .collect::<Vec<_>>();
+            *tensor_colors = new_tensor_colors;
+            *leg_colors = new_leg_colors;
+
+            let new_num_colors = count_distinct(tensor_colors) + count_distinct(leg_colors);
+            if new_num_colors == num_colors {
+                break;
+            }
+            num_colors = new_num_colors;
+        }
+    }
+}
+
+fn count_distinct(colors: &[u64]) -> usize {
+    let mut colors = colors.to_vec();
+    colors.sort_unstable();
+    colors.dedup();
+    colors.len()
+}
+
+/// Joins `values` with commas.
+fn join(values: impl IntoIterator<Item = impl ToString>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A canonical labeling of a [`TensorNetwork`].
+///
+/// Networks that only differ in the names of their legs and the order of their
+/// tensors, i.e. isomorphic networks, get the same hash. The order of the legs of a
+/// tensor is ignored, while the order of the output legs is not.
+///
+/// The canonical tensor order is found by color refinement. Tensors that color
+/// refinement cannot tell apart are ordered by individualizing them one at a time,
+/// which yields the same certificate for isomorphic networks unless refinement
+/// fails to separate tensors that are not symmetric to each other. In that rare
+/// case isomorphic networks can get different certificates, but never the other
+/// way around: equal certificates always mean isomorphic networks.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::canonical::CanonicalForm;
+/// let a = CanonicalForm::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2]],
+///     &[0],
+///     &FxHashMap::from_iter([(0, 2), (1, 10), (2, 3)]),
+/// )
+/// .unwrap();
+/// let b = CanonicalForm::from_usize_legs(
+///     &[vec![5], vec![7, 6], vec![6, 5]],
+///     &[7],
+///     &FxHashMap::from_iter([(5, 3), (6, 10), (7, 2)]),
+/// )
+/// .unwrap();
+/// assert_eq!(a.hash(), b.hash());
+/// assert!(a.is_isomorphic(&b));
+/// assert_eq!(a.canonical_network(), b.canonical_network());
+///
+/// // Tensor 0 of `a` corresponds to tensor 1 of `b`, and leg "0" to leg "7"
+/// let position = a.tensor_positions()[0];
+/// assert_eq!(b.tensor_order()[position], 1);
+/// let leg = a.leg_mapping()["0"];
+/// assert_eq!(b.leg_names()[leg], "7");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalForm {
+    hash: u64,
+    tensor_order: Vec<usize>,
+    tensor_positions: Vec<usize>,
+    leg_names: Vec<String>,
+    inputs: Vec<Vec<usize>>,
+    outputs: Vec<usize>,
+    sizes: Vec<u64>,
+    certificate: String,
+}
+
+impl CanonicalForm {
+    /// Computes the canonical labeling of a network with legs given as `usize`. The
+    /// legs are converted to strings with [`tensor_legs_to_digit`], which are the
+    /// names returned by [`Self::leg_names`] and [`Self::leg_mapping`].
+    ///
+    /// [`tensor_legs_to_digit`]: crate::utils::tensor_legs_to_digit
+    pub fn from_usize_legs(
+        inputs: &[Vec<usize>],
+        outputs: &[usize],
+        size_dict: &FxHashMap<usize, u64>,
+    ) -> Result<Self, NetworkError> {
+        let network = TensorNetwork::from_usize_legs(inputs, outputs, size_dict)?;
+        Ok(Self::new(&network))
+    }
+
+    /// Computes the canonical labeling of `network`.
+    pub fn new(network: &TensorNetwork) -> Self {
+        let graph = Graph::new(network);
+
+        let mut output_positions = vec![Vec::new(); graph.leg_names.len()];
+        let leg_ids = graph
+            .leg_names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.as_str(), id))
+            .collect::<FxHashMap<_, _>>();
+        for (position, leg) in network.outputs().iter().enumerate() {
+            output_positions[leg_ids[leg.as_str()]].push(position as u64);
+        }
+
+        let mut tensor_colors = graph
+            .tensor_legs
+            .iter()
+            .map(|legs| fnv1a([0, legs.len() as u64]))
+            .collect::<Vec<_>>();
+        let mut leg_colors = graph
+            .leg_names
+            .iter()
+            .zip(&output_positions)
+            .map(|(name, positions)| {
+                fnv1a(
+                    [1, network.size_dict()[name], positions.len() as u64]
+                        .into_iter()
+                        .chain(positions.iter().copied()),
+                )
+            })
+            .collect::<Vec<_>>();
+        graph.refine(&mut tensor_colors, &mut leg_colors);
+
+        let mut invariant = tensor_colors.clone();
+        invariant.sort_unstable();
+        let mut sorted_leg_colors = leg_colors.clone();
+        sorted_leg_colors.sort_unstable();
+        let hash = fnv1a(
+            [invariant.len() as u64, sorted_leg_colors.len() as u64]
+                .into_iter()
+                .chain(invariant)
+                .chain(sorted_leg_colors),
+        );
+
+        // Individualize the first tensor of the smallest ambiguous color until all
+        // tensors have distinct colors
+        loop {
+            let mut order = (0..tensor_colors.len()).collect::<Vec<_>>();
+            order.sort_by_key(|tensor| (tensor_colors[*tensor], *tensor));
+            let Some(tensor) = order
+                .windows(2)
+                .find(|pair| tensor_colors[pair[0]] == tensor_colors[pair[1]])
+                .map(|pair| pair[0])
+            else {
+                break;
+            };
+            tensor_colors[tensor] = fnv1a([tensor_colors[tensor], INDIVIDUALIZED]);
+            graph.refine(&mut tensor_colors, &mut leg_colors);
+        }
+
+        let mut tensor_order = (0..tensor_colors.len()).collect::<Vec<_>>();
+        tensor_order.sort_by_key(|tensor| tensor_colors[*tensor]);
+        let mut tensor_positions = vec![0; tensor_order.len()];
+        for (position, tensor) in tensor_order.iter().enumerate() {
+            tensor_positions[*tensor] = position;
+        }
+
+        // Legs of the same color are interchangeable once all tensors are distinct
+        let mut leg_order = (0..leg_colors.len()).collect::<Vec<_>>();
+        leg_order.sort_by_key(|leg| (leg_colors[*leg], *leg));
+        let mut leg_positions = vec![0; leg_order.len()];
+        for (position, leg) in leg_order.iter().enumerate() {
+            leg_positions[*leg] = position;
+        }
+
+        let inputs = tensor_order
+            .iter()
+            .map(|tensor| {
+                let mut legs = graph.tensor_legs[*tensor]
+                    .iter()
+                    .flat_map(|(leg, count)| {
+                        std::iter::repeat_n(leg_positions[*leg], *count as usize)
+                    })
+                    .collect::<Vec<_>>();
+                legs.sort_unstable();
+                legs
+            })
+            .collect::<Vec<_>>();
+        let outputs = network
+            .outputs()
+            .iter()
+            .map(|leg| leg_positions[leg_ids[leg.as_str()]])
+            .collect::<Vec<_>>();
+        let sizes = leg_order
+            .iter()
+            .map(|leg| network.size_dict()[&graph.leg_names[*leg]])
+            .collect::<Vec<_>>();
+        let certificate = format!(
+            "{}/{}/{}",
+            inputs.iter().map(join).collect::<Vec<_>>().join(";"),
+            join(&sizes),
+            join(&outputs)
+        );
+
+        let leg_names = leg_order
+            .iter()
+            .map(|leg| graph.leg_names[*leg].clone())
+            .collect();
+        Self {
+            hash,
+            tensor_order,
+            tensor_positions,
+            leg_names,
+            inputs,
+            outputs,
+            sizes,
+            certificate,
+        }
+    }
+
+    /// The hash of the network, equal for isomorphic networks. The hash is stable
+    /// across platforms and processes, but different networks can share a hash, so
+    /// use [`Self::is_isomorphic`] to compare networks.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// A string that is equal for two networks if and only if they are isomorphic,
+    /// up to the limitations described above.
+    pub fn certificate(&self) -> &str {
+        &self.certificate
+    }
+
+    /// Whether the network is isomorphic to the network of `other`.
+    pub fn is_isomorphic(&self, other: &Self) -> bool {
+        self.certificate == other.certificate
+    }
+
+    /// The tensor permutation: the original index of the tensor at each canonical
+    /// position.
+    pub fn tensor_order(&self) -> &[usize] {
+        &self.tensor_order
+    }
+
+    /// The inverse of [`Self::tensor_order`]: the canonical position of each
+    /// original tensor.
+    pub fn tensor_positions(&self) -> &[usize] {
+        &self.tensor_positions
+    }
+
+    /// The original name of each canonical leg.
+    pub fn leg_names(&self) -> &[String] {
+        &self.leg_names
+    }
+
+    /// The inverse of [`Self::leg_names`]: maps each original leg name to its
+    /// canonical leg.
+    pub fn leg_mapping(&self) -> FxHashMap<String, usize> {
+        self.leg_names
+            .iter()
+            .enumerate()
+            .map(|(leg, name)| (name.clone(), leg))
+            .collect()
+    }
+
+    /// The network in canonical form, with the tensors in canonical order and the
+    /// legs named by their canonical index. The legs of each tensor are sorted.
+    /// Isomorphic networks have the same canonical network.
+    pub fn canonical_network(&self) -> TensorNetwork {
+        let size_dict = self.sizes.iter().copied().enumerate().collect();
+        TensorNetwork::from_usize_legs(&self.inputs, &self.outputs, &size_dict)
+            .expect("canonical legs have sizes")
+    }
+
+    /// Converts an SSA path on the network to the canonical tensor order.
+    pub fn path_to_canonical(&self, ssa_path: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let relabel = |id: usize| self.tensor_positions.get(id).copied().unwrap_or(id);
+        ssa_path
+            .iter()
+            .map(|(i, j)| (relabel(*i), relabel(*j)))
+            .collect()
+    }
+
+    /// Converts an SSA path in the canonical tensor order to the network.
+    pub fn path_from_canonical(&self, ssa_path: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let relabel = |id: usize| self.tensor_order.get(id).copied().unwrap_or(id);
+        ssa_path
+            .iter()
+            .map(|(i, j)| (relabel(*i), relabel(*j)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(inputs: &[&[&str]], outputs: &[&str], sizes: &[(&str, u64)]) -> TensorNetwork {
+        TensorNetwork::new(
+            inputs
+                .iter()
+                .map(|legs| legs.iter().map(|leg| leg.to_string()).collect())
+                .collect(),
+            outputs.iter().map(|leg| leg.to_string()).collect(),
+            sizes
+                .iter()
+                .map(|(leg, size)| (leg.to_string(), *size))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_isomorphic_networks() {
+        let a = network(
+            &[&["a", "b"], &["b", "c"], &["c", "d", "a"], &["d"]],
+            &["a"],
+            &[("a", 2), ("b", 3), ("c", 4), ("d", 5)],
+        );
+        let b = network(
+            &[&["z"], &["y", "w"], &["x", "z", "w"], &["x", "y"]],
+            &["w"],
+            &[("w", 2), ("x", 4), ("y", 3), ("z", 5)],
+        );
+        let canonical_a = CanonicalForm::new(&a);
+        let canonical_b = CanonicalForm::new(&b);
+        assert_eq!(canonical_a.hash(), canonical_b.hash());
+        assert_eq!(canonical_a.certificate(), canonical_b.certificate());
+
+        // A path on `a` maps to an equivalent path on `b`
+        let path_a = [(0, 1), (2, 3), (4, 5)];
+        let canonical_path = canonical_a.path_to_canonical(&path_a);
+        assert_eq!(canonical_a.path_from_canonical(&canonical_path), path_a);
+        let path_b = canonical_b.path_from_canonical(&canonical_path);
+        assert_eq!(
+            crate::cost::contraction_cost(&a, &path_a),
+            crate::cost::contraction_cost(&b, &path_b)
+        );
+    }
+
+    #[test]
+    fn test_permutation_and_legs() {
+        let a = network(
+            &[&["a", "b", "b"], &["b", "c"], &["c", "a", "d"]],
+            &["d"],
+            &[("a", 2), ("b", 3), ("c", 4), ("d", 5)],
+        );
+        let b = CanonicalForm::from_usize_legs(
+            &[vec![1, 0], vec![0, 3, 2], vec![2, 1, 1]],
+            &[3],
+            &FxHashMap::from_iter([(0, 4), (1, 3), (2, 2), (3, 5)]),
+        )
+        .unwrap();
+        let canonical_a = CanonicalForm::new(&a);
+        assert!(canonical_a.is_isomorphic(&b));
+        assert_eq!(canonical_a.canonical_network(), b.canonical_network());
+
+        // Follow the tensor permutation and the leg mapping from `a` to `b`
+        let tensors = (0..3)
+            .map(|tensor| b.tensor_order()[canonical_a.tensor_positions()[tensor]])
+            .collect::<Vec<_>>();
+        assert_eq!(tensors, [2, 0, 1]);
+        let mapping = canonical_a.leg_mapping();
+        let legs = ["a", "b", "c", "d"].map(|leg| b.leg_names()[mapping[leg]].as_str());
+        assert_eq!(legs, ["2", "1", "0", "3"]);
+        for (position, tensor) in canonical_a.tensor_order().iter().enumerate() {
+            assert_eq!(canonical_a.tensor_positions()[*tensor], position);
+        }
+    }
+
+    #[test]
+    fn test_different_networks() {
+        let ring = |size| {
+            network(
+                &[&["a", "b"], &["b", "c"], &["c", "a"]],
+                &[],
+                &[("a", 2), ("b", 2), ("c", size)],
+            )
+        };
+        let canonical = CanonicalForm::new(&ring(2));
+        assert_ne!(canonical.hash(), CanonicalForm::new(&ring(3)).hash());
+
+        let open = network(
+            &[&["a", "b"], &["b", "c"], &["c", "a"]],
+            &["a"],
+            &[("a", 2), ("b", 2), ("c", 2)],
+        );
+        assert_ne!(canonical.hash(), CanonicalForm::new(&open).hash());
+    }
+
+    #[test]
+    fn test_symmetric_network() {
+        // A ring of identical tensors, where every rotation is an automorphism
+        let legs = ["a", "b", "c", "d", "e", "f"];
+        let inputs = (0..6)
+            .map(|tensor| vec![legs[tensor], legs[(tensor + 1) % 6]])
+            .collect::<Vec<_>>();
+        let rotated = (0..6)
+            .map(|tensor| vec![legs[(tensor + 2) % 6], legs[(tensor + 3) % 6]])
+            .rev()
+            .collect::<Vec<_>>();
+        let sizes = legs.map(|leg| (leg, 2));
+        let as_slices = |inputs: &[Vec<&'static str>]| {
+            let inputs = inputs
+                .iter()
+                .map(|legs| legs.as_slice())
+                .collect::<Vec<_>>();
+            network(&inputs, &[], &sizes)
+        };
+
+        let canonical = CanonicalForm::new(&as_slices(&inputs));
+        let canonical_rotated = CanonicalForm::new(&as_slices(&rotated));
+        assert_eq!(canonical.certificate(), canonical_rotated.certificate());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::network::TensorNetwork;
+use crate::utils::replace_to_ssa_path;
+
+/// The cost of contracting a tensor network along a contraction path.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ContractionCost {
+    /// The total number of scalar operations, i.e. the sum over all pairwise
+    /// contractions of the product of the sizes of all involved legs.
+    pub flops: f64,
+    /// The total number of elements written, i.e. the summed size of all
+    /// intermediate tensors.
+    pub write: f64,
+    /// The size of the largest intermediate tensor.
+    pub max_size: f64,
+    /// The largest number of elements that are alive at the same time, including
+    /// the input tensors that have not been contracted yet.
+    pub peak_memory: f64,
+}
+
+/// The quantity an optimizer minimizes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Minimize {
+    /// The total number of flops.
+    #[default]
+    Flops,
+    /// The total number of elements written.
+    Write,
+    /// The size of the largest intermediate tensor.
+    Size,
+    /// The total number of flops plus the given factor times the total write.
+    /// cotengra's `"combo"` uses a factor of 64.
+    Combo(f64),
+}
+
+impl Minimize {
+    /// The cotengra string of this target, e.g. `"flops"` or `"combo-64"`.
+    pub fn as_cotengra_str(&self) -> String {
+        match self {
+            Self::Flops => String::from("flops"),
+            Self::Write => String::from("write"),
+            Self::Size => String::from("size"),
+            Self::Combo(factor) => format!("combo-{factor}"),
+        }
+    }
+
+    /// The score of a single contraction with the given flops and result size.
+    /// Scores of contractions are summed, except for [`Minimize::Size`] where the
+    /// maximum is taken.
+    pub(crate) fn contraction_score(&self, flops: f64, size: f64) -> f64 {
+        match self {
+            Self::Flops => flops,
+            Self::Write | Self::Size => size,
+            Self::Combo(factor) => flops + factor * size,
+        }
+    }
+
+    /// Combines the scores of two parts of a contraction tree.
+    pub(crate) fn combine(&self, left: f64, right: f64) -> f64 {
+        match self {
+            Self::Size => left.max(right),
+            _ => left + right,
+        }
+    }
+}
+
+/// Computes the cost of contracting `network` along the SSA path `ssa_path`.
+///
+/// Partial paths are evaluated up to their last contraction.
+///
+/// # Panics
+/// Panics if the path contracts a tensor that does not exist or that has already
+/// been contracted.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::cost::contraction_cost;
+/// # use rustengra::network::TensorNetwork;
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2, 3]],
+///     &[0, 3],
+///     &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5)]),
+/// )
+/// .unwrap();
+/// let cost = contraction_cost(&network, &[(0, 1), (3, 2)]);
+/// assert_eq!(cost.flops, 64.0);
+/// assert_eq!(cost.write, 18.0);
+/// assert_eq!(cost.max_size, 10.0);
+/// assert_eq!(cost.peak_memory, 46.0);
+/// ```
+pub fn contraction_cost(network: &TensorNetwork, ssa_path: &[(usize, usize)]) -> ContractionCost {
+    let network = network.indexed();
+    let mut tensors = (0..network.num_tensors())
+        .map(|tensor| Some(network.input_legs(tensor).clone()))
+        .collect::<Vec<_>>();
+    let mut sizes = tensors
+        .iter()
+        .flatten()
+        .map(|legs| network.size(legs))
+        .collect::<Vec<_>>();
+
+    let mut cost = ContractionCost::default();
+    let mut alive = sizes.iter().sum::<f64>();
+    cost.peak_memory = alive;
+
+    for &(i, j) in ssa_path {
+        let mut take = |id: usize| {
+            tensors
+                .get_mut(id)
+                .and_then(Option::take)
+                .unwrap_or_else(|| panic!("Tensor {id} does not exist or was already contracted"))
+        };
+        let left = take(i);
+        let right = take(j);
+
+        let (legs, flops) = network.contract(&left, &right);
+        let size = network.size(&legs);
+        cost.flops += flops;
+        cost.write += size;
+        cost.max_size = cost.max_size.max(size);
+
+        alive += size;
+        cost.peak_memory = cost.peak_memory.max(alive);
+        alive -= sizes[i] + sizes[j];
+
+        tensors.push(Some(legs));
+        sizes.push(size);
+    }
+    cost
+}
+
+/// Computes the cost of contracting `network` along the replace left path
+/// `replace_path`. See [`contraction_cost`] for details.
+pub fn contraction_cost_replace(
+    network: &TensorNetwork,
+    replace_path: &[(usize, usize)],
+) -> ContractionCost {
+    let ssa_path = replace_to_ssa_path(replace_path.to_vec(), network.num_tensors());
+    contraction_cost(network, &ssa_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_hyperedge_cost() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0], vec![0], vec![0, 1]],
+            &[1],
+            &FxHashMap::from_iter([(0, 2), (1, 3)]),
+        )
+        .unwrap();
+
+        // The hyperedge is only summed over once all three tensors are contracted
+        let cost = contraction_cost(&network, &[(0, 1), (3, 2)]);
+        assert_eq!(cost.flops, 8.0);
+        assert_eq!(cost.write, 5.0);
+        assert_eq!(cost.max_size, 3.0);
+        assert_eq!(cost.peak_memory, 12.0);
+    }
+
+    #[test]
+    fn test_replace_path_cost() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0]],
+            &[],
+            &FxHashMap::from_iter([(0, 2), (1, 2), (2, 2), (3, 2)]),
+        )
+        .unwrap();
+
+        let ssa_path = [(0, 1), (2, 3), (4, 5)];
+        let replace_path = [(0, 1), (2, 3), (0, 2)];
+        assert_eq!(
+            contraction_cost(&network, &ssa_path),
+            contraction_cost_replace(&network, &replace_path)
+        );
+    }
+
+    #[test]
+    fn test_minimize_str() {
+        assert_eq!(Minimize::default().as_cotengra_str(), "flops");
+        assert_eq!(Minimize::Combo(64.0).as_cotengra_str(), "combo-64");
+        assert_eq!(Minimize::Combo(0.5).as_cotengra_str(), "combo-0.5");
+    }
+
+    #[test]
+    #[should_panic(expected = "already contracted")]
+    fn test_reused_tensor() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0], vec![0], vec![0]],
+            &[],
+            &FxHashMap::from_iter([(0, 2)]),
+        )
+        .unwrap();
+        contraction_cost(&network, &[(0, 1), (0, 2)]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::exceptions::{PyImportError, PyKeyError, PyTimeoutError, PyValueError};
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::cache::CacheError;
+use crate::network::NetworkError;
+use crate::utils::PathError;
+
+/// A Python exception raised while running cotengra.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonError {
+    /// The name of the exception type, e.g. `ValueError`.
+    pub kind: String,
+    /// The exception message.
+    pub message: String,
+    /// The formatted Python traceback, if available.
+    pub traceback: Option<String>,
+}
+
+#[cfg(feature = "python")]
+impl PythonError {
+    fn new(py: Python<'_>, err: &PyErr) -> Self {
+        let kind = err
+            .get_type(py)
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| String::from("<unknown>"));
+        let message = err.value(py).to_string();
+        let traceback = err.traceback(py).and_then(|tb| tb.format().ok());
+        Self {
+            kind,
+            message,
+            traceback,
+        }
+    }
+}
+
+impl fmt::Display for PythonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// The errors returned by this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The `cotengra` package could not be imported.
+    CotengraNotInstalled(PythonError),
+    /// An optional Python package needed by the requested method, e.g. `kahypar`,
+    /// could not be imported, or a needed executable was not found.
+    MissingDependency {
+        /// The name of the missing module or executable.
+        module: String,
+        error: PythonError,
+    },
+    /// cotengra rejected a value, e.g. an invalid contraction path (`ValueError`).
+    InvalidValue(PythonError),
+    /// cotengra looked up an unknown key, e.g. a leg without size (`KeyError`).
+    MissingKey(PythonError),
+    /// The optimizer ran out of time (`TimeoutError`).
+    Timeout(PythonError),
+    /// The optimizer raised any other exception.
+    Python(PythonError),
+    /// The tensor network is inconsistent.
+    Network(NetworkError),
+    /// A contraction path is invalid.
+    InvalidPath(PathError),
+    /// The on-disk path cache could not be read or written.
+    Cache(CacheError),
+}
+
+/// A `Result` with [`Error`] as error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Classifies the Python exception `err`.
+    #[cfg(feature = "python")]
+    pub fn from_py(py: Python<'_>, err: &PyErr) -> Self {
+        let error = PythonError::new(py, err);
+        if err.is_instance_of::<PyImportError>(py) {
+            // `ModuleNotFoundError` is a subclass of `ImportError`
+            let module = err
+                .value(py)
+                .getattr("name")
+                .and_then(|name| name.extract::<Option<String>>())
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            if module.split('.').next() == Some("cotengra") {
+                Self::CotengraNotInstalled(error)
+            } else {
+                Self::MissingDependency { module, error }
+            }
+        } else if err.is_instance_of::<PyValueError>(py) {
+            Self::InvalidValue(error)
+        } else if err.is_instance_of::<PyKeyError>(py) {
+            Self::MissingKey(error)
+        } else if err.is_instance_of::<PyTimeoutError>(py) {
+            Self::Timeout(error)
+        } else {
+            Self::Python(error)
+        }
+    }
+
+    /// The Python exception that caused this error, if any.
+    pub fn python_error(&self) -> Option<&PythonError> {
+        match self {
+            Self::CotengraNotInstalled(error)
+            | Self::MissingDependency { error, .. }
+            | Self::InvalidValue(error)
+            | Self::MissingKey(error)
+            | Self::Timeout(error)
+            | Self::Python(error) => Some(error),
+            Self::Network(_) | Self::InvalidPath(_) | Self::Cache(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<PyErr> for Error {
+    fn from(err: PyErr) -> Self {
+        Python::attach(|py| Self::from_py(py, &err))
+    }
+}
+
+impl From<NetworkError> for Error {
+    fn from(err: NetworkError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<PathError> for Error {
+    fn from(err: PathError) -> Self {
+        Self::InvalidPath(err)
+    }
+}
+
+impl From<CacheError> for Error {
+    fn from(err: CacheError) -> Self {
+        Self::Cache(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CotengraNotInstalled(error) => write!(f, "cotengra is not installed ({error})"),
+            Self::MissingDependency { module, error } => {
+                write!(f, "Python module {module:?} is not installed ({error})")
+            }
+            Self::InvalidValue(error)
+            | Self::MissingKey(error)
+            | Self::Timeout(error)
+            | Self::Python(error) => write!(f, "cotengra raised {error}"),
+            Self::Network(err) => write!(f, "invalid tensor network: {err}"),
+            Self::InvalidPath(err) => write!(f, "invalid contraction path: {err}"),
+            Self::Cache(err) => write!(f, "path cache failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            Self::InvalidPath(err) => Some(err),
+            Self::Cache(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "python"))]
+mod tests {
+    use super::*;
+
+    fn raise(code: &std::ffi::CStr) -> Error {
+        Python::initialize();
+        Python::attach(|py| py.run(code, None, None))
+            .unwrap_err()
+            .into()
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let err = raise(c"raise ModuleNotFoundError('no cotengra', name='cotengra')");
+        assert!(matches!(err, Error::CotengraNotInstalled(_)));
+
+        let err = raise(c"import rustengra_missing_module");
+        let Error::MissingDependency { module, error } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(module, "rustengra_missing_module");
+        assert_eq!(error.kind, "ModuleNotFoundError");
+    }
+
+    #[test]
+    fn test_exception_kinds() {
+        let err = raise(c"raise ValueError('invalid path')");
+        assert_eq!(
+            err.python_error().map(|error| error.message.as_str()),
+            Some("invalid path")
+        );
+        assert!(matches!(err, Error::InvalidValue(_)));
+        assert!(matches!(raise(c"{}['a']"), Error::MissingKey(_)));
+        assert!(matches!(raise(c"raise TimeoutError()"), Error::Timeout(_)));
+        assert!(matches!(raise(c"raise RuntimeError()"), Error::Python(_)));
+    }
+
+    #[test]
+    fn test_traceback() {
+        let err = raise(c"def f():\n    raise RuntimeError('inner')\nf()");
+        let traceback = err.python_error().unwrap().traceback.as_ref().unwrap();
+        assert!(traceback.contains("in f"));
+    }
+}
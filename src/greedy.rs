@@ -0,0 +1,299 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::network::{Legs, TensorNetwork};
+
+/// The options for the native greedy optimizer.
+///
+/// The options mirror the ones of cotengra's greedy optimizer. Please see the
+/// cotengra documentation for details on the parameters.
+#[derive(Debug, Clone)]
+pub struct GreedyOptions {
+    costmod: f64,
+    temperature: f64,
+    seed: Option<u64>,
+}
+
+impl Default for GreedyOptions {
+    fn default() -> Self {
+        Self {
+            costmod: 1.0,
+            temperature: 0.0,
+            seed: None,
+        }
+    }
+}
+
+impl GreedyOptions {
+    /// Creates the default greedy options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `costmod` argument, which weights the sizes of the contracted
+    /// tensors against the size of the result. Defaults to `1.0`.
+    pub fn with_costmod(mut self, costmod: f64) -> Self {
+        self.costmod = costmod;
+        self
+    }
+
+    /// Sets the `temperature` argument, which scales the Gumbel noise added to the
+    /// logarithm of each score. Defaults to `0.0`, i.e. a deterministic greedy
+    /// search.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Sets the seed of the noise.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// A candidate contraction, ordered by score and then by the ids of the tensors.
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    score: f64,
+    left: usize,
+    right: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| (self.left, self.right).cmp(&(other.left, other.right)))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Finds a contraction path for `network` with a greedy search, without calling
+/// into Python.
+///
+/// Repeatedly contracts the pair of tensors sharing a leg with the lowest score.
+/// Like in cotengra, the score is the sign-preserving logarithm `sign(x) * ln(1 +
+/// |x|)` of `x = size(ab) - costmod * (size(a) + size(b))`, minus Gumbel noise
+/// scaled by the temperature, so that the temperature does not depend on the
+/// scale of the tensor sizes. Once no tensors share legs anymore, the remaining
+/// tensors are contracted in order of increasing size. Returns the path in SSA
+/// format.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::greedy::{greedy_path, GreedyOptions};
+/// # use rustengra::network::TensorNetwork;
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2]],
+///     &[0],
+///     &FxHashMap::from_iter([(0, 2), (1, 10), (2, 3)]),
+/// )
+/// .unwrap();
+/// let path = greedy_path(&network, &GreedyOptions::default());
+/// assert_eq!(path, vec![(0, 1), (2, 3)]);
+/// ```
+pub fn greedy_path(network: &TensorNetwork, options: &GreedyOptions) -> Vec<(usize, usize)> {
+    let network = network.indexed();
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    let mut tensors = (0..network.num_tensors())
+        .map(|tensor| Some(network.input_legs(tensor).clone()))
+        .collect::<Vec<_>>();
+    let mut sizes = tensors
+        .iter()
+        .flatten()
+        .map(|legs| network.size(legs))
+        .collect::<Vec<_>>();
+
+    let mut leg_tensors = FxHashMap::<usize, FxHashSet<usize>>::default();
+    for (tensor, legs) in tensors.iter().enumerate() {
+        for (leg, _) in legs.iter().flatten() {
+            leg_tensors.entry(*leg).or_default().insert(tensor);
+        }
+    }
+
+    let mut score = |left: &Legs, right: &Legs, size_left: f64, size_right: f64| {
+        let (legs, _) = network.contract(left, right);
+        let score = network.size(&legs) - options.costmod * (size_left + size_right);
+        let mut score = score.signum() * score.abs().ln_1p();
+        if options.temperature > 0.0 {
+            let gumbel = -(-rng.random::<f64>().ln()).ln();
+            score -= options.temperature * gumbel;
+        }
+        score
+    };
+
+    let mut queue = BinaryHeap::new();
+    let mut push_candidates = |queue: &mut BinaryHeap<_>,
+                               tensors: &[Option<Legs>],
+                               sizes: &[f64],
+                               leg_tensors: &FxHashMap<usize, FxHashSet<usize>>,
+                               tensor: usize| {
+        let legs = tensors[tensor].as_ref().unwrap();
+        let mut neighbors = legs
+            .iter()
+            .flat_map(|(leg, _)| &leg_tensors[leg])
+            .copied()
+            .filter(|other| *other != tensor)
+            .collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        for other in neighbors {
+            let other_legs = tensors[other].as_ref().unwrap();
+            queue.push(Reverse(Candidate {
+                score: score(other_legs, legs, sizes[other], sizes[tensor]),
+                left: other.min(tensor),
+                right: other.max(tensor),
+            }));
+        }
+    };
+
+    for tensor in 0..tensors.len() {
+        push_candidates(&mut queue, &tensors, &sizes, &leg_tensors, tensor);
+    }
+
+    let mut path = Vec::with_capacity(tensors.len().saturating_sub(1));
+    let mut contract = |tensors: &mut Vec<Option<Legs>>,
+                        sizes: &mut Vec<f64>,
+                        leg_tensors: &mut FxHashMap<usize, FxHashSet<usize>>,
+                        left: usize,
+                        right: usize| {
+        let left_legs = tensors[left].take().unwrap();
+        let right_legs = tensors[right].take().unwrap();
+        let (legs, _) = network.contract(&left_legs, &right_legs);
+
+        let id = tensors.len();
+        for (leg, _) in left_legs.iter().chain(&right_legs) {
+            let users = leg_tensors.get_mut(leg).unwrap();
+            users.remove(&left);
+            users.remove(&right);
+        }
+        for (leg, _) in &legs {
+            leg_tensors.get_mut(leg).unwrap().insert(id);
+        }
+
+        path.push((left, right));
+        sizes.push(network.size(&legs));
+        tensors.push(Some(legs));
+        id
+    };
+
+    while let Some(Reverse(Candidate { left, right, .. })) = queue.pop() {
+        if tensors[left].is_none() || tensors[right].is_none() {
+            continue;
+        }
+        let id = contract(&mut tensors, &mut sizes, &mut leg_tensors, left, right);
+        push_candidates(&mut queue, &tensors, &sizes, &leg_tensors, id);
+    }
+
+    // Contract the remaining disconnected tensors, smallest first
+    let mut remaining = (0..tensors.len())
+        .filter(|tensor| tensors[*tensor].is_some())
+        .map(|tensor| {
+            Reverse(Candidate {
+                score: sizes[tensor],
+                left: tensor,
+                right: tensor,
+            })
+        })
+        .collect::<BinaryHeap<_>>();
+    while remaining.len() > 1 {
+        let Reverse(Candidate { left, .. }) = remaining.pop().unwrap();
+        let Reverse(Candidate { left: right, .. }) = remaining.pop().unwrap();
+        let id = contract(&mut tensors, &mut sizes, &mut leg_tensors, left, right);
+        remaining.push(Reverse(Candidate {
+            score: sizes[id],
+            left: id,
+            right: id,
+        }));
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::validate_ssa_path;
+
+    fn network() -> TensorNetwork {
+        TensorNetwork::from_usize_legs(
+            &[
+                vec![0, 1, 2],
+                vec![2, 3],
+                vec![3, 4, 5],
+                vec![1, 4],
+                vec![5, 6],
+                vec![7],
+                vec![7, 8],
+            ],
+            &[0, 6],
+            &FxHashMap::from_iter([
+                (0, 2),
+                (1, 3),
+                (2, 4),
+                (3, 5),
+                (4, 2),
+                (5, 3),
+                (6, 4),
+                (7, 5),
+                (8, 2),
+            ]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_greedy_path() {
+        let network = network();
+        let path = greedy_path(&network, &GreedyOptions::default());
+        assert_eq!(validate_ssa_path(&path, &network), Ok(()));
+        assert_eq!(path[0], (1, 2));
+        // The two disconnected parts are joined last
+        assert_eq!(path.len(), 6);
+        assert_eq!(path[5], (10, 11));
+    }
+
+    #[test]
+    fn test_seeded_temperature() {
+        let network = network();
+        let options = GreedyOptions::new().with_temperature(1.0).with_seed(42);
+        let path = greedy_path(&network, &options);
+        assert_eq!(validate_ssa_path(&path, &network), Ok(()));
+        assert_eq!(path, greedy_path(&network, &options));
+    }
+
+    #[test]
+    fn test_temperature_is_scale_invariant() {
+        // The scores of this chain differ by thousands, so noise on the raw scores
+        // would never change the path
+        let legs = (0..8)
+            .map(|tensor| vec![tensor, tensor + 1])
+            .collect::<Vec<_>>();
+        let sizes = FxHashMap::from_iter((0..9).map(|leg| (leg, 1000 + 7 * leg as u64)));
+        let network = TensorNetwork::from_usize_legs(&legs, &[0, 8], &sizes).unwrap();
+        let paths = (0..20)
+            .map(|seed| {
+                let options = GreedyOptions::new().with_temperature(1.0).with_seed(seed);
+                greedy_path(&network, &options)
+            })
+            .collect::<FxHashSet<_>>();
+        assert!(paths.len() > 1);
+    }
+}
@@ -1,18 +1,279 @@
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use pyo3::{prelude::*, types::PyDict};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt};
 use rustc_hash::FxHashMap;
 
+use crate::cache::PathCache;
+use crate::cost::Minimize;
+use crate::network::TensorNetwork;
+use crate::optimizer::OptimizationResult;
+use crate::reconf::ReconfOptions;
+use crate::slicing::{SliceReconfOptions, SlicingOptions};
+use crate::{cached, validated, Error, PythonError, Result};
+
+/// A method the HyperOptimizer samples trees from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HyperMethod {
+    /// cotengra's greedy optimizer with sampled cost parameters.
+    Greedy,
+    /// Repeated greedy runs with random noise, see [`HyperMethod::Greedy`].
+    RandomGreedy,
+    /// Requires the `kahypar` package.
+    Kahypar,
+    /// Requires the `kahypar` package.
+    KahyparBalanced,
+    /// Requires the `kahypar` package.
+    KahyparAgglom,
+    /// Partitions the network by label propagation.
+    Labels,
+    /// Requires the `igraph` package.
+    Spinglass,
+    /// Requires the `igraph` package.
+    Betweenness,
+    /// Requires the `igraph` package.
+    Walktrap,
+    /// Requires the `quickbb_64` executable.
+    Quickbb,
+    /// Requires the `flow_cutter_pace17` executable.
+    Flowcutter,
+}
+
+/// Something a [`HyperMethod`] needs besides cotengra.
+enum Dependency {
+    Module(&'static str),
+    Executable(&'static str),
+}
+
+impl HyperMethod {
+    /// The corresponding method name of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Greedy => "greedy",
+            Self::RandomGreedy => "random-greedy",
+            Self::Kahypar => "kahypar",
+            Self::KahyparBalanced => "kahypar-balanced",
+            Self::KahyparAgglom => "kahypar-agglom",
+            Self::Labels => "labels",
+            Self::Spinglass => "spinglass",
+            Self::Betweenness => "betweenness",
+            Self::Walktrap => "walktrap",
+            Self::Quickbb => "quickbb",
+            Self::Flowcutter => "flowcutter",
+        }
+    }
+
+    fn dependency(&self) -> Option<Dependency> {
+        match self {
+            Self::Greedy | Self::RandomGreedy | Self::Labels => None,
+            Self::Kahypar | Self::KahyparBalanced | Self::KahyparAgglom => {
+                Some(Dependency::Module("kahypar"))
+            }
+            Self::Spinglass | Self::Betweenness | Self::Walktrap => {
+                Some(Dependency::Module("igraph"))
+            }
+            Self::Quickbb => Some(Dependency::Executable("quickbb_64")),
+            Self::Flowcutter => Some(Dependency::Executable("flow_cutter_pace17")),
+        }
+    }
+
+    /// Checks that the Python package or executable backing this method is
+    /// available. Fails with [`Error::MissingDependency`] otherwise.
+    pub fn check_available(&self) -> Result<()> {
+        Python::initialize();
+        Python::attach(|py| self.check(py))
+    }
+
+    fn check(&self, py: Python<'_>) -> Result<()> {
+        match self.dependency() {
+            None => Ok(()),
+            Some(Dependency::Module(module)) => PyModule::import(py, module)
+                .map(|_| ())
+                .map_err(|err| Error::from_py(py, &err)),
+            Some(Dependency::Executable(executable)) => {
+                let path = PyModule::import(py, "shutil")?.call_method1("which", (executable,))?;
+                if path.is_none() {
+                    return Err(Error::MissingDependency {
+                        module: executable.to_owned(),
+                        error: PythonError {
+                            kind: String::from("FileNotFoundError"),
+                            message: format!("executable {executable:?} is not on the PATH"),
+                            traceback: None,
+                        },
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The library the HyperOptimizer uses to sample hyper parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Optlib {
+    Optuna,
+    /// Samples uniformly at random. Needs no additional package.
+    Random,
+    Cmaes,
+    Nevergrad,
+    Skopt,
+    Baytune,
+}
+
+impl Optlib {
+    /// All sampling libraries.
+    pub const ALL: [Self; 6] = [
+        Self::Optuna,
+        Self::Random,
+        Self::Cmaes,
+        Self::Nevergrad,
+        Self::Skopt,
+        Self::Baytune,
+    ];
+
+    /// The corresponding string argument of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Optuna => "optuna",
+            Self::Random => "random",
+            Self::Cmaes => "cmaes",
+            Self::Nevergrad => "nevergrad",
+            Self::Skopt => "skopt",
+            Self::Baytune => "baytune",
+        }
+    }
+
+    /// The Python module backing the library, if any.
+    fn module(&self) -> Option<&'static str> {
+        match self {
+            Self::Optuna => Some("optuna"),
+            Self::Random => None,
+            Self::Cmaes => Some("cmaes"),
+            Self::Nevergrad => Some("nevergrad"),
+            Self::Skopt => Some("skopt"),
+            Self::Baytune => Some("btb"),
+        }
+    }
+
+    fn is_importable(&self, py: Python<'_>) -> bool {
+        self.module()
+            .is_none_or(|module| PyModule::import(py, module).is_ok())
+    }
+
+    /// Whether the Python package backing the library can be imported.
+    pub fn is_available(&self) -> bool {
+        Python::initialize();
+        Python::attach(|py| self.is_importable(py))
+    }
+
+    /// All sampling libraries that can be imported.
+    pub fn available() -> Vec<Self> {
+        Python::initialize();
+        Python::attach(|py| {
+            Self::ALL
+                .into_iter()
+                .filter(|optlib| optlib.is_importable(py))
+                .collect()
+        })
+    }
+
+    /// This library if it can be imported. Optuna, the default of cotengra, falls
+    /// back to [`Optlib::Random`], while any other library fails with
+    /// [`Error::MissingDependency`].
+    fn resolve(self, py: Python<'_>) -> Result<Self> {
+        let Some(module) = self.module() else {
+            return Ok(self);
+        };
+        match PyModule::import(py, module) {
+            Ok(_) => Ok(self),
+            Err(_) if self == Self::Optuna => Ok(Self::Random),
+            Err(err) => Err(Error::from_py(py, &err)),
+        }
+    }
+}
+
+/// The time budget of the HyperOptimizer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxTime {
+    /// Stops the search after the given time.
+    Seconds(Duration),
+    /// Stops the search once the time spent on the search exceeds the estimated
+    /// contraction time of the best tree, assuming the given number of flops per
+    /// second (`"rate:{rate}"`).
+    Rate(f64),
+    /// Stops the search once the best score has not improved for the given number
+    /// of trials (`"equil:{trials}"`).
+    Equilibrium(usize),
+}
+
+impl From<Duration> for MaxTime {
+    fn from(time: Duration) -> Self {
+        Self::Seconds(time)
+    }
+}
+
+impl From<&Duration> for MaxTime {
+    fn from(time: &Duration) -> Self {
+        Self::Seconds(*time)
+    }
+}
+
+impl MaxTime {
+    /// Sets the `max_time` keyword argument in `kwargs`.
+    fn set_kwarg(&self, kwargs: &Bound<'_, PyDict>) -> PyResult<()> {
+        match self {
+            Self::Seconds(time) => kwargs.set_item("max_time", time.as_secs_f64()),
+            Self::Rate(rate) => kwargs.set_item("max_time", format!("rate:{rate:e}")),
+            Self::Equilibrium(trials) => kwargs.set_item("max_time", format!("equil:{trials}")),
+        }
+    }
+}
+
+/// How the HyperOptimizer handles an exception raised by a single trial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnTrialError {
+    /// Warns and continues with the next trial.
+    #[default]
+    Warn,
+    /// Aborts the search and propagates the exception.
+    Raise,
+    /// Silently continues with the next trial.
+    Ignore,
+}
+
+impl OnTrialError {
+    /// The corresponding string argument of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Raise => "raise",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
 /// The keyword options for the cotengra Hyperoptimizer.
 ///
 /// Unassigned options will not be passed to the function and hence the Python
 /// default values will be used. Please see the cotengra documentation for details on
 /// the parameters.
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct HyperOptions {
-    max_time: Option<u64>,
+    max_time: Option<MaxTime>,
     max_repeats: Option<usize>,
     parallel: Option<bool>,
+    minimize: Option<Minimize>,
+    max_size: Option<u64>,
+    slicing_opts: Option<SlicingOptions>,
+    slicing_reconf_opts: Option<SliceReconfOptions>,
+    reconf_opts: Option<ReconfOptions>,
+    optlib: Option<Optlib>,
+    on_trial_error: Option<OnTrialError>,
+    score_compression: Option<f64>,
+    progbar: Option<bool>,
+    directory: Option<PathBuf>,
+    overwrite: Option<bool>,
 }
 
 impl HyperOptions {
@@ -21,9 +282,10 @@ impl HyperOptions {
         Self::default()
     }
 
-    /// Sets the `max_time` argument for the HyperOptimizer.
-    pub fn with_max_time(mut self, time: &Duration) -> Self {
-        self.max_time = Some(time.as_secs());
+    /// Sets the `max_time` argument for the HyperOptimizer. Accepts a [`Duration`],
+    /// which keeps sub-second precision, or any other [`MaxTime`].
+    pub fn with_max_time(mut self, time: impl Into<MaxTime>) -> Self {
+        self.max_time = Some(time.into());
         self
     }
 
@@ -38,45 +300,421 @@ impl HyperOptions {
         self.parallel = Some(parallel);
         self
     }
+
+    /// Sets the `minimize` argument for the HyperOptimizer, the score to minimize.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `max_size` argument for the HyperOptimizer, the maximum size of any
+    /// intermediate tensor a trial may produce.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets the `slicing_opts` argument for the HyperOptimizer, which slices every
+    /// trial tree.
+    pub fn with_slicing_opts(mut self, slicing_opts: SlicingOptions) -> Self {
+        self.slicing_opts = Some(slicing_opts);
+        self
+    }
+
+    /// Sets the `slicing_reconf_opts` argument for the HyperOptimizer, which
+    /// alternately slices and reconfigures every trial tree.
+    pub fn with_slicing_reconf_opts(mut self, slicing_reconf_opts: SliceReconfOptions) -> Self {
+        self.slicing_reconf_opts = Some(slicing_reconf_opts);
+        self
+    }
+
+    /// Sets the `reconf_opts` argument for the HyperOptimizer, which reconfigures
+    /// every trial tree.
+    pub fn with_reconf_opts(mut self, reconf_opts: ReconfOptions) -> Self {
+        self.reconf_opts = Some(reconf_opts);
+        self
+    }
+
+    /// Sets the `optlib` argument for the HyperOptimizer, the library used to
+    /// sample the hyper parameters. Falls back to [`Optlib::Random`] if
+    /// [`Optlib::Optuna`] cannot be imported, while any other library that cannot be
+    /// imported fails the search with [`Error::MissingDependency`].
+    pub fn with_optlib(mut self, optlib: Optlib) -> Self {
+        self.optlib = Some(optlib);
+        self
+    }
+
+    /// Sets the `on_trial_error` argument for the HyperOptimizer.
+    pub fn with_on_trial_error(mut self, on_trial_error: OnTrialError) -> Self {
+        self.on_trial_error = Some(on_trial_error);
+        self
+    }
+
+    /// Sets the `score_compression` argument for the HyperOptimizer.
+    pub fn with_score_compression(mut self, score_compression: f64) -> Self {
+        self.score_compression = Some(score_compression);
+        self
+    }
+
+    /// Sets the `progbar` argument for the HyperOptimizer.
+    pub fn with_progbar(mut self, progbar: bool) -> Self {
+        self.progbar = Some(progbar);
+        self
+    }
+
+    /// Sets the `directory` argument, the directory in which found paths are cached
+    /// across processes. Switches to cotengra's `ReusableHyperOptimizer`, which
+    /// returns the cached path if the same network was optimized before.
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Sets the `overwrite` argument, whether to search again and replace cached
+    /// paths. Only modifies the cache set with [`HyperOptions::with_directory`] and
+    /// is ignored without a directory.
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = Some(overwrite);
+        self
+    }
+
+    /// Whether cotengra's `ReusableHyperOptimizer` is used.
+    fn is_reusable(&self) -> bool {
+        self.directory.is_some()
+    }
+}
+
+/// The value of a sampled hyper parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HyperParam {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// A string parameter, or the `str()` of any other Python value.
+    Str(String),
+}
+
+impl<'py> FromPyObject<'_, 'py> for HyperParam {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, 'py, PyAny>) -> PyResult<Self> {
+        // `bool` is a subclass of `int` in Python, so it has to be checked first
+        Ok(if obj.is_instance_of::<PyBool>() {
+            Self::Bool(obj.extract()?)
+        } else if obj.is_instance_of::<PyInt>() {
+            Self::Int(obj.extract()?)
+        } else if obj.is_instance_of::<PyFloat>() {
+            Self::Float(obj.extract()?)
+        } else {
+            Self::Str(obj.str()?.to_string())
+        })
+    }
+}
+
+/// A single trial of the HyperOptimizer.
+///
+/// The costs are logarithmic, as reported by cotengra.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperTrial {
+    /// The method of the trial, e.g. `greedy` or `kahypar`.
+    pub method: String,
+    /// The hyper parameters sampled for the method.
+    pub params: FxHashMap<String, HyperParam>,
+    /// The log10 of the number of flops of the trial tree.
+    pub log10_flops: f64,
+    /// The log10 of the total size of all intermediate tensors of the trial tree.
+    pub log10_write: f64,
+    /// The log2 of the size of the largest intermediate tensor of the trial tree.
+    pub log2_size: f64,
+    /// The score of the trial, lower is better.
+    pub score: f64,
+    /// The wall time of the trial. Zero if cotengra reported a negative, infinite or
+    /// NaN time.
+    pub time: Duration,
+}
+
+/// The result of a HyperOptimizer search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperResult {
+    /// The best contraction path found.
+    pub result: OptimizationResult,
+    /// All trials of the search, in the order they were reported. Empty if the
+    /// `ReusableHyperOptimizer` was used, since it does not expose its trials.
+    pub trials: Vec<HyperTrial>,
+    /// The sampling library that was used, if one was requested. Is
+    /// [`Optlib::Random`] if [`Optlib::Optuna`] was requested but could not be
+    /// imported.
+    pub optlib: Option<Optlib>,
+}
+
+impl HyperResult {
+    /// The trial with the lowest score, if any trial succeeded.
+    pub fn best_trial(&self) -> Option<&HyperTrial> {
+        self.trials
+            .iter()
+            .min_by(|a, b| a.score.total_cmp(&b.score))
+    }
+}
+
+/// The name of a search over `methods`, e.g. `greedy,kahypar`.
+fn method_name(methods: &[HyperMethod]) -> String {
+    methods
+        .iter()
+        .map(HyperMethod::as_cotengra_str)
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
-/// Runs the Hyperoptimizer of cotengra on the given inputs. Additional inputs to the
-/// Hyperoptimizer can be passed with the [`HyperOptions`] struct.
+/// Collects the trials recorded by the Python HyperOptimizer `opt`.
+fn trials(opt: &Bound<'_, PyAny>) -> PyResult<Vec<HyperTrial>> {
+    let methods: Vec<String> = opt.getattr("method_choices")?.extract()?;
+    let params: Vec<FxHashMap<String, HyperParam>> = opt.getattr("param_choices")?.extract()?;
+    let flops: Vec<f64> = opt.getattr("costs_flops")?.extract()?;
+    let write: Vec<f64> = opt.getattr("costs_write")?.extract()?;
+    let size: Vec<f64> = opt.getattr("costs_size")?.extract()?;
+    let scores: Vec<f64> = opt.getattr("scores")?.extract()?;
+    let times: Vec<f64> = opt.getattr("times")?.extract()?;
+
+    Ok(methods
+        .into_iter()
+        .zip(params)
+        .zip(flops.into_iter().zip(write).zip(size))
+        .zip(scores.into_iter().zip(times))
+        .map(
+            |(((method, params), ((flops, write), size)), (score, time))| HyperTrial {
+                method,
+                params,
+                log10_flops: flops,
+                log10_write: write,
+                log2_size: size,
+                score,
+                time: Duration::try_from_secs_f64(time).unwrap_or(Duration::ZERO),
+            },
+        )
+        .collect())
+}
+
+/// Runs the Hyperoptimizer of cotengra on the given network, sampling trees from all
+/// of the given `methods`. Additional inputs to the Hyperoptimizer can be passed
+/// with the [`HyperOptions`] struct. If `validate` is
+/// set, the returned path is checked with
+/// [`validate_ssa_path`](crate::utils::validate_ssa_path). If `cache` is given, the
+/// path is stored in it and the cached path is returned instead if it is better,
+/// see [`PathCache::improve`]. Returns the best path together with all trials of
+/// the search. Fails with [`Error::InvalidValue`] if `methods` is empty.
 ///
 /// # Python Dependency
-/// Python 3 must be installed with `cotengra` and `kahypar` packages installed.
+/// Python 3 must be installed with the `cotengra` package and the dependencies of
+/// the chosen methods, e.g. `kahypar`. Fails with [`Error::MissingDependency`]
+/// before the search if a dependency is missing.
 /// Can also work with virtual environments if the binary is run from a terminal with
 /// actived virtual environment.
 pub fn cotengra_hyperoptimizer(
-    inputs: &[Vec<String>],
-    outputs: &[String],
-    size_dict: &FxHashMap<String, u64>,
-    method: &str,
+    network: &TensorNetwork,
+    methods: &[HyperMethod],
     options: &HyperOptions,
-) -> PyResult<Vec<(usize, usize)>> {
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<HyperResult> {
+    if methods.is_empty() {
+        return Err(Error::InvalidValue(PythonError {
+            kind: String::from("ValueError"),
+            message: String::from("at least one method is required"),
+            traceback: None,
+        }));
+    }
+    let start = Instant::now();
     Python::initialize();
-    let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
+    let optlib = Python::attach(|py| {
+        methods.iter().try_for_each(|method| method.check(py))?;
+        options.optlib.map(|optlib| optlib.resolve(py)).transpose()
+    })?;
+    let (contraction_path, trials) = Python::attach(|py| -> PyResult<_> {
         let cotengra = PyModule::import(py, "cotengra")?;
 
-        let args = (inputs, outputs, size_dict).into_pyobject(py)?;
+        let args = (network.inputs(), network.outputs(), network.size_dict()).into_pyobject(py)?;
 
         let kwargs = PyDict::new(py);
-        kwargs.set_item("methods", method)?;
+        let method_names = methods
+            .iter()
+            .map(HyperMethod::as_cotengra_str)
+            .collect::<Vec<_>>();
+        kwargs.set_item("methods", method_names)?;
         if let Some(max_repeats) = options.max_repeats {
             kwargs.set_item("max_repeats", max_repeats)?;
         }
         if let Some(max_time) = options.max_time {
-            kwargs.set_item("max_time", max_time)?;
+            max_time.set_kwarg(&kwargs)?;
         }
         if let Some(parallel) = options.parallel {
             kwargs.set_item("parallel", parallel)?;
         }
+        if let Some(minimize) = options.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(max_size) = options.max_size {
+            kwargs.set_item("max_size", max_size)?;
+        }
+        if let Some(slicing_opts) = &options.slicing_opts {
+            kwargs.set_item("slicing_opts", slicing_opts.to_kwargs(py)?)?;
+        }
+        if let Some(slicing_reconf_opts) = &options.slicing_reconf_opts {
+            kwargs.set_item("slicing_reconf_opts", slicing_reconf_opts.to_kwargs(py)?)?;
+        }
+        if let Some(reconf_opts) = &options.reconf_opts {
+            kwargs.set_item("reconf_opts", reconf_opts.to_kwargs(py)?)?;
+        }
+        if let Some(optlib) = optlib {
+            kwargs.set_item("optlib", optlib.as_cotengra_str())?;
+        }
+        if let Some(on_trial_error) = options.on_trial_error {
+            kwargs.set_item("on_trial_error", on_trial_error.as_cotengra_str())?;
+        }
+        if let Some(score_compression) = options.score_compression {
+            kwargs.set_item("score_compression", score_compression)?;
+        }
+        if let Some(progbar) = options.progbar {
+            kwargs.set_item("progbar", progbar)?;
+        }
+        if let Some(directory) = &options.directory {
+            kwargs.set_item("directory", directory)?;
+        }
+        if let Some(overwrite) = options.overwrite.filter(|_| options.is_reusable()) {
+            kwargs.set_item("overwrite", overwrite)?;
+        }
 
-        let opt = cotengra.call_method("HyperOptimizer", (), Some(&kwargs))?;
-        opt.call_method1("search", args)?
+        let reusable = options.is_reusable();
+        let class = if reusable {
+            "ReusableHyperOptimizer"
+        } else {
+            "HyperOptimizer"
+        };
+        let opt = cotengra.call_method(class, (), Some(&kwargs))?;
+        let contraction_path: Vec<(usize, usize)> = opt
+            .call_method1("search", args)?
             .call_method0("get_ssa_path")?
-            .extract()
+            .extract()?;
+        let trials = if reusable { Vec::new() } else { trials(&opt)? };
+        Ok((contraction_path, trials))
     })?;
 
-    Ok(contraction_path)
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(network, path, &method_name(methods), None, elapsed);
+    Ok(HyperResult {
+        result: cached(network, result, cache),
+        trials,
+        optlib,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_time_kwarg() {
+        Python::initialize();
+        Python::attach(|py| {
+            let max_time = |time: MaxTime| {
+                let kwargs = PyDict::new(py);
+                time.set_kwarg(&kwargs).unwrap();
+                kwargs.get_item("max_time").unwrap().unwrap().to_string()
+            };
+            assert_eq!(max_time(Duration::from_millis(500).into()), "0.5");
+            assert_eq!(max_time(MaxTime::Rate(1e9)), "rate:1e9");
+            assert_eq!(max_time(MaxTime::Equilibrium(64)), "equil:64");
+        });
+    }
+
+    #[test]
+    fn test_reusable_needs_directory() {
+        assert!(!HyperOptions::default().with_overwrite(true).is_reusable());
+        let options = HyperOptions::default()
+            .with_directory("paths")
+            .with_overwrite(true);
+        assert!(options.is_reusable());
+    }
+
+    #[test]
+    fn test_methods() {
+        assert_eq!(
+            method_name(&[HyperMethod::KahyparAgglom, HyperMethod::RandomGreedy]),
+            "kahypar-agglom,random-greedy"
+        );
+        assert_eq!(HyperMethod::Greedy.check_available(), Ok(()));
+
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0], vec![0]],
+            &[],
+            &FxHashMap::from_iter([(0, 2)]),
+        )
+        .unwrap();
+        let err = cotengra_hyperoptimizer(&network, &[], &HyperOptions::default(), true, None)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidValue(error) if error.kind == "ValueError"));
+    }
+
+    #[test]
+    fn test_optlib_fallback() {
+        assert!(Optlib::available().contains(&Optlib::Random));
+        Python::initialize();
+        Python::attach(|py| {
+            assert_eq!(Optlib::Random.resolve(py), Ok(Optlib::Random));
+            for optlib in Optlib::ALL {
+                let resolved = optlib.resolve(py);
+                if optlib.is_importable(py) {
+                    assert_eq!(resolved, Ok(optlib));
+                } else if optlib == Optlib::Optuna {
+                    assert_eq!(resolved, Ok(Optlib::Random));
+                } else {
+                    let Err(Error::MissingDependency { module, .. }) = resolved else {
+                        panic!("{optlib:?} resolved to {resolved:?}");
+                    };
+                    assert_eq!(Some(module.as_str()), optlib.module());
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_trials() {
+        Python::initialize();
+        let trials = Python::attach(|py| {
+            let locals = PyDict::new(py);
+            py.run(
+                c"from types import SimpleNamespace
+opt = SimpleNamespace(
+    method_choices=['greedy', 'kahypar'],
+    param_choices=[{'random_strength': 0.1, 'costmod': 1, 'flag': True}, {'mode': 'direct'}],
+    costs_flops=[3.0, 2.5],
+    costs_write=[2.0, 1.5],
+    costs_size=[4.0, 3.0],
+    scores=[3.0, 2.5],
+    times=[0.25, 0.5],
+)",
+                None,
+                Some(&locals),
+            )?;
+            trials(&locals.get_item("opt")?.unwrap())
+        })
+        .unwrap();
+
+        assert_eq!(trials.len(), 2);
+        assert_eq!(trials[0].method, "greedy");
+        assert_eq!(trials[0].params["random_strength"], HyperParam::Float(0.1));
+        assert_eq!(trials[0].params["costmod"], HyperParam::Int(1));
+        assert_eq!(trials[0].params["flag"], HyperParam::Bool(true));
+        assert_eq!(trials[1].params["mode"], HyperParam::Str("direct".into()));
+        assert_eq!(trials[1].log2_size, 3.0);
+        assert_eq!(trials[1].time, Duration::from_millis(500));
+
+        let network = TensorNetwork::from_usize_legs(&[], &[], &FxHashMap::default()).unwrap();
+        let result = HyperResult {
+            result: OptimizationResult::new(&network, vec![], "hyper", None, Duration::ZERO),
+            trials,
+            optlib: None,
+        };
+        assert_eq!(result.best_trial().unwrap().method, "kahypar");
+    }
 }
@@ -1,12 +1,44 @@
+#[cfg(feature = "python")]
+use std::time::Instant;
+
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
 use pyo3::types::PyDict;
-use rustc_hash::FxHashMap;
-
-use crate::utils::replace_to_ssa_path;
 
+#[cfg(feature = "python")]
+use crate::annealing::{AnnealingOptions, InitialTree, TemperingOptions};
+#[cfg(feature = "python")]
+use crate::cache::PathCache;
+#[cfg(feature = "python")]
+use crate::network::TensorNetwork;
+#[cfg(feature = "python")]
+use crate::optimizer::OptimizationResult;
+#[cfg(feature = "python")]
+use crate::reconf::{ForestOptions, ReconfOptions};
+#[cfg(feature = "python")]
+use crate::slicing::{sliced_legs, SliceReconfOptions, SliceReconfResult, SlicedContraction};
+#[cfg(feature = "python")]
+use crate::utils::{replace_to_ssa_path, validate_ssa_path};
+
+pub mod annealing;
+pub mod cache;
+pub mod canonical;
+pub mod cost;
+mod error;
+pub mod greedy;
+#[cfg(feature = "python")]
 pub mod hyper;
+pub mod network;
+pub mod optimizer;
+pub mod reconf;
+pub mod slicing;
+pub mod tree;
 pub mod utils;
 
+pub use error::{Error, PythonError, Result};
+
+#[cfg(feature = "python")]
 /// Checks if Cotengra is installed in the current environment.
 ///
 /// # Example
@@ -14,11 +46,13 @@ pub mod utils;
 /// # use rustengra::cotengra_check;
 /// assert!(cotengra_check().is_ok());
 /// ```
-pub fn cotengra_check() -> PyResult<()> {
+pub fn cotengra_check() -> Result<()> {
     Python::initialize();
-    Python::attach(|py| PyModule::import(py, "cotengra").map(|_| ()))
+    Python::attach(|py| PyModule::import(py, "cotengra").map(|_| ()))?;
+    Ok(())
 }
 
+#[cfg(feature = "python")]
 /// Information about the used Python interpreter.
 ///
 /// The fields correspond to the attributes of the Python `sys` module.
@@ -29,10 +63,11 @@ pub struct PythonInfo {
     pub path: Vec<String>,
 }
 
+#[cfg(feature = "python")]
 /// Obtains information about the Python interpreter being used by this lib.
-pub fn python_info() -> PyResult<PythonInfo> {
+pub fn python_info() -> Result<PythonInfo> {
     Python::initialize();
-    Python::attach(|py| {
+    Python::attach(|py| -> PyResult<_> {
         let sys = PyModule::import(py, "sys")?;
         let executable = sys.getattr("executable")?.extract()?;
         let version = sys.getattr("version")?.extract()?;
@@ -43,80 +78,165 @@ pub fn python_info() -> PyResult<PythonInfo> {
             path,
         })
     })
+    .map_err(Error::from)
 }
 
+#[cfg(feature = "python")]
 /// Accepts tensor network information and returns an optimized ContractionTree via Cotengra.
 ///
-/// Accepts inputs as `iterable[iterable[str]]`, output as `iterable[str]`, a size dict as `dict[str, int]`,
-/// a starting path as `vec![(usize, usize)]`, the subtree size for optimization as `u64` and `is_ssa` as bool.
+/// Accepts a [`TensorNetwork`], a starting path as `vec![(usize, usize)]`, the
+/// [`ReconfOptions`] of the optimization and `is_ssa` as bool.
 /// Creates a `ContractionTree` in Cotengra and calls `subtree_reconfigure` to find an improved
-/// Contraction. Returns a `PyResult` of the best new contraction path in SSA format.
-/// If input !`is_ssa` converts it to an SSA path.
+/// Contraction. Returns the best new contraction path as [`OptimizationResult`].
+/// If input !`is_ssa` converts it to an SSA path. If `validate` is set, the returned
+/// path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the path is stored in it and the cached path is returned
+/// instead if it is better, see [`PathCache::improve`].
 pub fn cotengra_optimize_from_path(
-    inputs: &[Vec<String>],
-    outputs: &[String],
-    size_dict: &FxHashMap<String, u64>,
+    network: &TensorNetwork,
     path: Vec<(usize, usize)>,
-    subtree_size: usize,
+    options: &ReconfOptions,
     is_ssa: bool,
-) -> PyResult<Vec<(usize, usize)>> {
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<OptimizationResult> {
+    let start = Instant::now();
     Python::initialize();
     let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
-        let cotengra = PyModule::import(py, "cotengra")?;
-
-        let kwargs = PyDict::new(py);
-        kwargs.set_item("size_dict", size_dict)?;
+        let opt_kwargs = options.to_kwargs(py)?;
+        opt_kwargs.set_item("inplace", true)?;
 
-        let path = if is_ssa {
-            path
-        } else {
-            replace_to_ssa_path(path, inputs.len())
-        };
+        cotengra_tree_from_path(py, network, path, is_ssa)?
+            .call_method("subtree_reconfigure", (), Some(&opt_kwargs))?
+            .call_method0("get_ssa_path")?
+            .extract()
+    })?;
 
-        kwargs.set_item("ssa_path", path)?;
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(network, path, "subtree-reconf", options.seed(), elapsed);
+    Ok(cached(network, result, cache))
+}
 
-        let opt_kwargs = PyDict::new(py);
-        opt_kwargs.set_item("subtree_size", subtree_size)?;
+#[cfg(feature = "python")]
+/// Accepts tensor network information and returns an optimized ContractionTree via
+/// Cotengra.
+///
+/// Like [`cotengra_optimize_from_path`], but calls `subtree_reconfigure_forest`,
+/// which reconfigures a forest of trees in parallel and periodically restarts from
+/// the best ones. The forest is configured with [`ForestOptions`]. Returns the best
+/// contraction path as [`OptimizationResult`]. If `validate` is set, the returned
+/// path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the path is stored in it and the cached path is returned
+/// instead if it is better, see [`PathCache::improve`].
+pub fn cotengra_reconfigure_forest(
+    network: &TensorNetwork,
+    path: Vec<(usize, usize)>,
+    options: &ForestOptions,
+    is_ssa: bool,
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<OptimizationResult> {
+    let start = Instant::now();
+    Python::initialize();
+    let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
+        let opt_kwargs = options.to_kwargs(py)?;
         opt_kwargs.set_item("inplace", true)?;
 
-        let args = (inputs, outputs).into_pyobject(py)?;
-        cotengra
-            .getattr("ContractionTree")?
-            .getattr("from_path")?
-            .call(args, Some(&kwargs))?
-            .call_method("subtree_reconfigure", (), Some(&opt_kwargs))?
+        cotengra_tree_from_path(py, network, path, is_ssa)?
+            .call_method("subtree_reconfigure_forest", (), Some(&opt_kwargs))?
             .call_method0("get_ssa_path")?
             .extract()
     })?;
 
-    Ok(contraction_path)
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(
+        network,
+        path,
+        "subtree-reconf-forest",
+        options.seed(),
+        elapsed,
+    );
+    Ok(cached(network, result, cache))
+}
+
+#[cfg(feature = "python")]
+/// Accepts tensor network information and returns a sliced and optimized
+/// ContractionTree via Cotengra.
+///
+/// Like [`cotengra_optimize_from_path`], but alternately slices the tree and applies
+/// subtree reconfiguration until no intermediate tensor exceeds the `target_size`
+/// of `options`. The reconfiguration is configured with
+/// [`SliceReconfOptions::with_reconf_opts`]. Returns the final
+/// contraction path as [`OptimizationResult`] together with the sliced legs.
+/// If `validate` is set, the returned path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the reconfigured path is stored in it, see
+/// [`PathCache::update`].
+pub fn cotengra_slice_and_reconfigure(
+    network: &TensorNetwork,
+    path: Vec<(usize, usize)>,
+    options: &SliceReconfOptions,
+    is_ssa: bool,
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<SliceReconfResult> {
+    let start = Instant::now();
+    Python::initialize();
+    let (contraction_path, sliced): (Vec<(usize, usize)>, _) = Python::attach(|py| {
+        let opt_kwargs = options.to_kwargs(py)?;
+
+        let tree = cotengra_tree_from_path(py, network, path, is_ssa)?.call_method(
+            "slice_and_reconfigure",
+            (),
+            Some(&opt_kwargs),
+        )?;
+        PyResult::Ok((
+            tree.call_method0("get_ssa_path")?.extract()?,
+            sliced_legs(&tree)?,
+        ))
+    })?;
+
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let sliced = SlicedContraction::new(network, &path, sliced)?;
+    if let Some(cache) = cache {
+        // Failures of the cache do not fail the optimization
+        let _ = cache.update(network, &path);
+    }
+    Ok(SliceReconfResult {
+        result: OptimizationResult::new(network, path, "slice-reconf", None, elapsed),
+        sliced,
+    })
 }
 
+#[cfg(feature = "python")]
 /// Accepts tensor network information and returns an optimized ContractionTree via
 /// Cotengra.
 ///
-/// Accepts inputs as `iterable[iterable[char]]`, output as `iterable[char]`, a
-/// `size_dict` that maps from `char` to `u64` and a subtree size for optimization.
+/// Accepts a [`TensorNetwork`] and the [`ReconfOptions`] of the optimization.
 /// Creates a ContractionTree in Cotengra by a Greedy method and optimizes it with
-/// subtree reconfiguration. Returns a PyResult of the optimized tree converted to a
-/// SSA path.
+/// subtree reconfiguration. Returns the optimized path as [`OptimizationResult`].
+/// If `validate` is set, the returned path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the path is stored in it and the cached path is returned
+/// instead if it is better, see [`PathCache::improve`].
 pub fn cotengra_optimized_greedy(
-    inputs: &[Vec<String>],
-    outputs: &[String],
-    size_dict: &FxHashMap<String, u64>,
-    subtree_size: usize,
-) -> PyResult<Vec<(usize, usize)>> {
+    network: &TensorNetwork,
+    options: &ReconfOptions,
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<OptimizationResult> {
+    let start = Instant::now();
     Python::initialize();
     let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
         let cotengra = PyModule::import(py, "cotengra")?;
 
-        let args = (inputs, outputs, size_dict).into_pyobject(py)?;
+        let args = (network.inputs(), network.outputs(), network.size_dict()).into_pyobject(py)?;
 
         let kwargs = PyDict::new(py);
         kwargs.set_item("optimize", String::from("greedy"))?;
 
-        let opt_kwargs = PyDict::new(py);
-        opt_kwargs.set_item("subtree_size", subtree_size)?;
+        let opt_kwargs = options.to_kwargs(py)?;
         opt_kwargs.set_item("inplace", true)?;
 
         cotengra
@@ -127,124 +247,155 @@ pub fn cotengra_optimized_greedy(
             .extract()
     })?;
 
-    Ok(contraction_path)
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(network, path, "greedy+reconf", options.seed(), elapsed);
+    Ok(cached(network, result, cache))
 }
 
+#[cfg(feature = "python")]
 /// Accepts tensor network information and returns an optimized ContractionTree via
 /// Cotengra.
 ///
-/// Accepts inputs as `iterable[iterable[char]]`, output as `iterable[char]`, a
-/// `size_dict` that maps from `char` to `u64` and a subtree size for optimization.
-/// Creates a ContractionTree in Cotengra by a Greedy method and optimizes it with
-/// simualted annealing. Returns a PyResult of the optimized tree converted to a
-/// SSA path.
+/// Accepts a [`TensorNetwork`], the [`InitialTree`] to start from and the
+/// [`AnnealingOptions`].
+/// Creates the initial ContractionTree in Cotengra and optimizes it with
+/// simualted annealing. Returns the optimized path as [`OptimizationResult`].
+/// If `validate` is set, the returned path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the path is stored in it and the cached path is returned
+/// instead if it is better, see [`PathCache::improve`].
 pub fn cotengra_sa_tree(
-    inputs: &[Vec<String>],
-    outputs: &[String],
-    steps: Option<usize>,
-    iter: Option<usize>,
-    size_dict: &FxHashMap<String, u64>,
-    seed: Option<u64>,
-) -> PyResult<Vec<(usize, usize)>> {
+    network: &TensorNetwork,
+    initial: &InitialTree,
+    options: &AnnealingOptions,
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<OptimizationResult> {
+    let start = Instant::now();
     Python::initialize();
     let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
         let cotengra = PyModule::import(py, "cotengra")?;
 
-        let args = (inputs, outputs, size_dict).into_pyobject(py)?;
+        let args = (initial.build(py, network)?,).into_pyobject(py)?;
 
-        let kwargs = PyDict::new(py);
-        kwargs.set_item("optimize", String::from("greedy"))?;
+        let kwargs = options.to_kwargs(py)?;
+        kwargs.set_item("inplace", true)?;
 
-        let tree_obj = cotengra
-            .getattr("array_contract_tree")?
-            .call(args, Some(&kwargs))?;
-        let args = (tree_obj,).into_pyobject(py)?;
-
-        if let Some(seed) = seed {
-            let kwargs = PyDict::new(py);
-            kwargs.set_item("seed", seed)?;
-
-            if let Some(steps) = steps {
-                kwargs.set_item("tsteps", steps)?;
-            }
-
-            if let Some(iter) = iter {
-                kwargs.set_item("numiter", iter)?;
-            }
-            kwargs.set_item("inplace", true)?;
-
-            cotengra
-                .getattr("pathfinders")?
-                .getattr("path_simulated_annealing")?
-                .call_method("simulated_anneal_tree", args, Some(&kwargs))?
-                .call_method0("get_ssa_path")?
-                .extract()
-        } else {
-            cotengra
-                .getattr("pathfinders")?
-                .getattr("path_simulated_annealing")?
-                .call_method1("simulated_anneal_tree", args)?
-                .call_method0("get_ssa_path")?
-                .extract()
-        }
+        cotengra
+            .getattr("pathfinders")?
+            .getattr("path_simulated_annealing")?
+            .call_method("simulated_anneal_tree", args, Some(&kwargs))?
+            .call_method0("get_ssa_path")?
+            .extract()
     })?;
 
-    Ok(contraction_path)
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(
+        network,
+        path,
+        "simulated-annealing",
+        options.seed(),
+        elapsed,
+    );
+    Ok(cached(network, result, cache))
 }
 
+#[cfg(feature = "python")]
 /// Accepts tensor network information and returns an optimized ContractionTree via
 /// Cotengra.
 ///
-/// Accepts inputs as `iterable[iterable[char]]`, output as `iterable[char]`, a
-/// `size_dict` that maps from `char` to `u64` and a subtree size for optimization.
-/// Creates a ContractionTree in Cotengra by simulated annealing and optimizes it
-/// using tree tempering. Returns a PyResult of the optimized tree converted to a SSA
-/// path.
+/// Accepts a [`TensorNetwork`], the [`InitialTree`] to start from and the
+/// [`TemperingOptions`].
+/// Creates the initial ContractionTree in Cotengra and optimizes it using tree
+/// tempering. Returns the optimized path as [`OptimizationResult`].
+/// If `validate` is set, the returned path is checked with [`validate_ssa_path`].
+/// If `cache` is given, the path is stored in it and the cached path is returned
+/// instead if it is better, see [`PathCache::improve`].
 pub fn cotengra_tree_tempering(
-    inputs: &[Vec<String>],
-    outputs: &[String],
-    iter: Option<usize>,
-    size_dict: &FxHashMap<String, u64>,
-    seed: Option<u64>,
-) -> PyResult<Vec<(usize, usize)>> {
+    network: &TensorNetwork,
+    initial: &InitialTree,
+    options: &TemperingOptions,
+    validate: bool,
+    cache: Option<&PathCache>,
+) -> Result<OptimizationResult> {
+    let start = Instant::now();
     Python::initialize();
     let contraction_path: Vec<(usize, usize)> = Python::attach(|py| {
         let cotengra = PyModule::import(py, "cotengra")?;
 
-        let args = (inputs, outputs, size_dict).into_pyobject(py)?;
+        let args = (initial.build(py, network)?,).into_pyobject(py)?;
 
-        let kwargs = PyDict::new(py);
-        kwargs.set_item("optimize", String::from("greedy"))?;
+        let kwargs = options.to_kwargs(py)?;
+        kwargs.set_item("inplace", true)?;
 
-        let tree_obj = cotengra
-            .getattr("array_contract_tree")?
-            .call(args, Some(&kwargs))?;
-        let args = (tree_obj,).into_pyobject(py)?;
-
-        if let Some(seed) = seed {
-            let kwargs = PyDict::new(py);
-            kwargs.set_item("seed", seed)?;
-
-            if let Some(iter) = iter {
-                kwargs.set_item("numiter", iter)?;
-            }
-            kwargs.set_item("inplace", true)?;
-
-            cotengra
-                .getattr("pathfinders")?
-                .getattr("path_simulated_annealing")?
-                .call_method("parallel_temper_tree", args, Some(&kwargs))?
-                .call_method0("get_ssa_path")?
-                .extract()
-        } else {
-            cotengra
-                .getattr("pathfinders")?
-                .getattr("path_simulated_annealing")?
-                .call_method1("parallel_temper_tree", args)?
-                .call_method0("get_ssa_path")?
-                .extract()
-        }
+        cotengra
+            .getattr("pathfinders")?
+            .getattr("path_simulated_annealing")?
+            .call_method("parallel_temper_tree", args, Some(&kwargs))?
+            .call_method0("get_ssa_path")?
+            .extract()
     })?;
 
-    Ok(contraction_path)
+    let elapsed = start.elapsed();
+    let path = validated(network, contraction_path, validate)?;
+    let result = OptimizationResult::new(network, path, "tempering", options.seed(), elapsed);
+    Ok(cached(network, result, cache))
+}
+
+#[cfg(feature = "python")]
+/// Stores the path of `result` in `cache` and returns the cached path instead if it
+/// is better. Failures of the cache are ignored.
+pub(crate) fn cached(
+    network: &TensorNetwork,
+    result: OptimizationResult,
+    cache: Option<&PathCache>,
+) -> OptimizationResult {
+    let Some(cache) = cache else {
+        return result;
+    };
+    match cache.improve(network, &result.ssa_path) {
+        Ok(Some(cached)) => {
+            OptimizationResult::new(network, cached.ssa_path, "cache", None, result.elapsed)
+        }
+        Ok(None) | Err(_) => result,
+    }
+}
+
+#[cfg(feature = "python")]
+/// Builds the Python `ContractionTree` of `network` contracted along `path`, which
+/// is converted to an SSA path unless `is_ssa` is set.
+pub(crate) fn cotengra_tree_from_path<'py>(
+    py: Python<'py>,
+    network: &TensorNetwork,
+    path: Vec<(usize, usize)>,
+    is_ssa: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let path = if is_ssa {
+        path
+    } else {
+        replace_to_ssa_path(path, network.num_tensors())
+    };
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("size_dict", network.size_dict())?;
+    kwargs.set_item("ssa_path", path)?;
+    let args = (network.inputs(), network.outputs()).into_pyobject(py)?;
+    PyModule::import(py, "cotengra")?
+        .getattr("ContractionTree")?
+        .getattr("from_path")?
+        .call(args, Some(&kwargs))
+}
+
+#[cfg(feature = "python")]
+/// Checks `path` with [`validate_ssa_path`] if `validate` is set.
+pub(crate) fn validated(
+    network: &TensorNetwork,
+    path: Vec<(usize, usize)>,
+    validate: bool,
+) -> Result<Vec<(usize, usize)>> {
+    if validate {
+        validate_ssa_path(&path, network)?;
+    }
+    Ok(path)
 }
@@ -0,0 +1,342 @@
+use std::fmt;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::utils::tensor_legs_to_digit;
+
+/// Reasons why a set of tensors, open legs and leg sizes does not form a valid
+/// [`TensorNetwork`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkError {
+    /// A leg of an input tensor has no entry in the size dict.
+    MissingSize(String),
+    /// An output leg does not appear on any input tensor.
+    UnknownOutput(String),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSize(leg) => write!(f, "leg {leg:?} has no size"),
+            Self::UnknownOutput(leg) => {
+                write!(f, "output leg {leg:?} does not appear in any input tensor")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// A tensor network contraction problem.
+///
+/// Owns the legs of each input tensor, the open (output) legs and the size of every
+/// leg. The three parts are checked for consistency on construction, so every
+/// optimizer can rely on them agreeing with each other.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::network::TensorNetwork;
+/// let network = TensorNetwork::new(
+///     vec![vec![String::from("a"), String::from("b")], vec![String::from("b")]],
+///     vec![String::from("a")],
+///     FxHashMap::from_iter([(String::from("a"), 2), (String::from("b"), 3)]),
+/// )
+/// .unwrap();
+/// assert_eq!(network.num_tensors(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TensorNetwork {
+    inputs: Vec<Vec<String>>,
+    outputs: Vec<String>,
+    size_dict: FxHashMap<String, u64>,
+}
+
+impl TensorNetwork {
+    /// Creates a new tensor network from the legs of each input tensor, the output
+    /// legs and the size of each leg.
+    ///
+    /// Fails if a leg of an input tensor has no size or if an output leg does not
+    /// appear in any of the input tensors. Sizes of legs that are not used are
+    /// allowed.
+    pub fn new(
+        inputs: Vec<Vec<String>>,
+        outputs: Vec<String>,
+        size_dict: FxHashMap<String, u64>,
+    ) -> Result<Self, NetworkError> {
+        let mut input_legs = FxHashSet::default();
+        for leg in inputs.iter().flatten() {
+            if !size_dict.contains_key(leg) {
+                return Err(NetworkError::MissingSize(leg.clone()));
+            }
+            input_legs.insert(leg);
+        }
+
+        if let Some(leg) = outputs.iter().find(|leg| !input_legs.contains(leg)) {
+            return Err(NetworkError::UnknownOutput(leg.clone()));
+        }
+
+        Ok(Self {
+            inputs,
+            outputs,
+            size_dict,
+        })
+    }
+
+    /// Creates a new tensor network from legs given as `usize`. The legs are
+    /// converted to strings with [`tensor_legs_to_digit`].
+    pub fn from_usize_legs(
+        inputs: &[Vec<usize>],
+        outputs: &[usize],
+        size_dict: &FxHashMap<usize, u64>,
+    ) -> Result<Self, NetworkError> {
+        if let Some(leg) = inputs
+            .iter()
+            .flatten()
+            .find(|leg| !size_dict.contains_key(leg))
+        {
+            return Err(NetworkError::MissingSize(leg.to_string()));
+        }
+
+        let (inputs, outputs, size_dict) = tensor_legs_to_digit(inputs, outputs, size_dict);
+        Self::new(inputs, outputs, size_dict)
+    }
+
+    /// The legs of each input tensor.
+    pub fn inputs(&self) -> &[Vec<String>] {
+        &self.inputs
+    }
+
+    /// The open legs of the network.
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// The size of each leg.
+    pub fn size_dict(&self) -> &FxHashMap<String, u64> {
+        &self.size_dict
+    }
+
+    /// The number of input tensors.
+    pub fn num_tensors(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Decomposes the network into inputs, outputs and size dict.
+    pub fn into_parts(self) -> (Vec<Vec<String>>, Vec<String>, FxHashMap<String, u64>) {
+        (self.inputs, self.outputs, self.size_dict)
+    }
+
+    /// Assigns each input tensor the id of its connected component, where tensors
+    /// are connected if they share a leg. Components are numbered in the order of
+    /// their first tensor.
+    pub fn connected_components(&self) -> Vec<usize> {
+        fn find(parents: &mut [usize], mut tensor: usize) -> usize {
+            while parents[tensor] != tensor {
+                parents[tensor] = parents[parents[tensor]];
+                tensor = parents[tensor];
+            }
+            tensor
+        }
+
+        let mut parents = (0..self.inputs.len()).collect::<Vec<_>>();
+        let mut first_tensor = FxHashMap::default();
+        for (tensor, legs) in self.inputs.iter().enumerate() {
+            for leg in legs {
+                let other = *first_tensor.entry(leg).or_insert(tensor);
+                let (root, other_root) = (find(&mut parents, tensor), find(&mut parents, other));
+                parents[root.max(other_root)] = root.min(other_root);
+            }
+        }
+
+        let mut ids = FxHashMap::default();
+        (0..self.inputs.len())
+            .map(|tensor| {
+                let root = find(&mut parents, tensor);
+                let next_id = ids.len();
+                *ids.entry(root).or_insert(next_id)
+            })
+            .collect()
+    }
+
+    /// Converts the network to the integer leg representation used by the native
+    /// algorithms.
+    pub(crate) fn indexed(&self) -> IndexedNetwork {
+        let mut ids = FxHashMap::default();
+        let mut names = Vec::new();
+        let mut sizes = Vec::new();
+        let mut counts = Vec::new();
+
+        let mut leg_id = |leg: &String| {
+            *ids.entry(leg.clone()).or_insert_with(|| {
+                names.push(leg.clone());
+                sizes.push(self.size_dict[leg] as f64);
+                counts.push(0);
+                names.len() - 1
+            })
+        };
+
+        let mut tensors = Vec::with_capacity(self.inputs.len());
+        for tensor in &self.inputs {
+            let mut legs: Legs = Vec::with_capacity(tensor.len());
+            for leg in tensor {
+                let id = leg_id(leg);
+                match legs.iter_mut().find(|(other, _)| *other == id) {
+                    Some((_, count)) => *count += 1,
+                    None => legs.push((id, 1)),
+                }
+            }
+            legs.sort_unstable();
+            tensors.push(legs);
+        }
+        let outputs = self.outputs.iter().map(&mut leg_id).collect::<Vec<_>>();
+
+        for (id, count) in tensors.iter().flatten() {
+            counts[*id] += count;
+        }
+        for id in outputs {
+            counts[id] += 1;
+        }
+
+        IndexedNetwork {
+            names,
+            sizes,
+            counts,
+            tensors,
+        }
+    }
+}
+
+/// Legs of a tensor as pairs of leg id and the number of times the leg appears in
+/// the input tensors that were contracted into it, sorted by leg id.
+pub(crate) type Legs = Vec<(usize, usize)>;
+
+/// A [`TensorNetwork`] with legs replaced by consecutive ids.
+///
+/// Output legs count as an additional appearance of the leg, so they are never
+/// contracted away.
+#[derive(Debug, Clone)]
+pub(crate) struct IndexedNetwork {
+    names: Vec<String>,
+    sizes: Vec<f64>,
+    counts: Vec<usize>,
+    tensors: Vec<Legs>,
+}
+
+impl IndexedNetwork {
+    /// The number of input tensors.
+    pub(crate) fn num_tensors(&self) -> usize {
+        self.tensors.len()
+    }
+
+    /// The legs of the input tensor `tensor`.
+    pub(crate) fn input_legs(&self, tensor: usize) -> &Legs {
+        &self.tensors[tensor]
+    }
+
+    /// The name of the leg with id `leg`.
+    pub(crate) fn leg_name(&self, leg: usize) -> &str {
+        &self.names[leg]
+    }
+
+    /// The number of elements of a tensor with the given legs.
+    pub(crate) fn size(&self, legs: &Legs) -> f64 {
+        legs.iter().map(|(leg, _)| self.sizes[*leg]).product()
+    }
+
+    /// Contracts two tensors, returning the legs of the result and the flops of the
+    /// contraction, which is the product of the sizes of all involved legs.
+    pub(crate) fn contract(&self, left: &Legs, right: &Legs) -> (Legs, f64) {
+        let mut legs = Vec::with_capacity(left.len() + right.len());
+        let mut flops = 1.0;
+        let mut keep = |leg: usize, count: usize| {
+            flops *= self.sizes[leg];
+            if count < self.counts[leg] {
+                legs.push((leg, count));
+            }
+        };
+
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            let (l, lc) = left[i];
+            let (r, rc) = right[j];
+            if l < r {
+                keep(l, lc);
+                i += 1;
+            } else if r < l {
+                keep(r, rc);
+                j += 1;
+            } else {
+                keep(l, lc + rc);
+                i += 1;
+                j += 1;
+            }
+        }
+        for &(leg, count) in left[i..].iter().chain(&right[j..]) {
+            keep(leg, count);
+        }
+
+        (legs, flops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legs(legs: &[&str]) -> Vec<String> {
+        legs.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_missing_size() {
+        let network = TensorNetwork::new(
+            vec![legs(&["a", "b"]), legs(&["b", "c"])],
+            legs(&["a"]),
+            FxHashMap::from_iter([(String::from("a"), 2), (String::from("b"), 2)]),
+        );
+        assert_eq!(network, Err(NetworkError::MissingSize(String::from("c"))));
+    }
+
+    #[test]
+    fn test_unknown_output() {
+        let network = TensorNetwork::new(
+            vec![legs(&["a", "b"]), legs(&["b"])],
+            legs(&["a", "d"]),
+            FxHashMap::from_iter([
+                (String::from("a"), 2),
+                (String::from("b"), 2),
+                (String::from("d"), 2),
+            ]),
+        );
+        assert_eq!(network, Err(NetworkError::UnknownOutput(String::from("d"))));
+    }
+
+    #[test]
+    fn test_from_usize_legs() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2]],
+            &[0, 2],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4)]),
+        )
+        .unwrap();
+        assert_eq!(network.inputs(), &[legs(&["0", "1"]), legs(&["1", "2"])]);
+        assert_eq!(network.outputs(), &legs(&["0", "2"]));
+        assert_eq!(network.size_dict()["1"], 3);
+
+        let network =
+            TensorNetwork::from_usize_legs(&[vec![0, 1]], &[], &FxHashMap::from_iter([(0, 2)]));
+        assert_eq!(network, Err(NetworkError::MissingSize(String::from("1"))));
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0], vec![1, 2], vec![3], vec![2, 0], vec![4]],
+            &[],
+            &FxHashMap::from_iter([(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)]),
+        )
+        .unwrap();
+        assert_eq!(network.connected_components(), vec![0, 0, 1, 0, 2]);
+    }
+}
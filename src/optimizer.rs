@@ -0,0 +1,340 @@
+use std::time::Duration;
+
+#[cfg(feature = "python")]
+use crate::annealing::{AnnealingOptions, InitialTree, TemperingOptions};
+use crate::cache::{CachedOptimizer, PathCache};
+use crate::cost::contraction_cost;
+use crate::greedy::{greedy_path, GreedyOptions};
+#[cfg(feature = "python")]
+use crate::hyper::{cotengra_hyperoptimizer, HyperMethod, HyperOptions};
+use crate::network::TensorNetwork;
+#[cfg(feature = "python")]
+use crate::reconf::ForestOptions;
+use crate::reconf::{subtree_reconfigure, ReconfOptions};
+use crate::tree::ContractionTree;
+use crate::utils::{ssa_to_replace_path, validate_ssa_path};
+use crate::Result;
+#[cfg(feature = "python")]
+use crate::{
+    cotengra_optimize_from_path, cotengra_optimized_greedy, cotengra_reconfigure_forest,
+    cotengra_sa_tree, cotengra_tree_tempering,
+};
+
+/// A contraction path in SSA format.
+pub type ContractionPath = Vec<(usize, usize)>;
+
+/// A contraction path together with its cost and how it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizationResult {
+    /// The contraction path in SSA format.
+    pub ssa_path: ContractionPath,
+    /// The contraction path in replace left format.
+    pub replace_path: Vec<(usize, usize)>,
+    /// The log10 of the number of flops of the contraction.
+    pub log10_flops: f64,
+    /// The size of the largest intermediate tensor.
+    pub max_size: f64,
+    /// The summed size of all intermediate tensors.
+    pub total_write: f64,
+    /// The name of the method that found the path.
+    pub method: String,
+    /// The seed passed to the method, if any.
+    pub seed: Option<u64>,
+    /// The wall time spent finding the path.
+    pub elapsed: Duration,
+}
+
+impl OptimizationResult {
+    /// Collects the result for `ssa_path` on `network` and computes its costs. The
+    /// costs are NaN if the path is not a valid SSA path, e.g. if an optimizer
+    /// was run without validation. `log10_flops` is negative infinity if the path
+    /// has no flops, e.g. the empty path of a single tensor.
+    pub fn new(
+        network: &TensorNetwork,
+        ssa_path: ContractionPath,
+        method: &str,
+        seed: Option<u64>,
+        elapsed: Duration,
+    ) -> Self {
+        let (log10_flops, max_size, total_write) = if validate_ssa_path(&ssa_path, network).is_ok()
+        {
+            let cost = contraction_cost(network, &ssa_path);
+            (cost.flops.log10(), cost.max_size, cost.write)
+        } else {
+            (f64::NAN, f64::NAN, f64::NAN)
+        };
+        Self {
+            replace_path: ssa_to_replace_path(ssa_path.clone(), network.num_tensors()),
+            ssa_path,
+            log10_flops,
+            max_size,
+            total_write,
+            method: method.to_owned(),
+            seed,
+            elapsed,
+        }
+    }
+}
+
+/// A method to find a contraction path for a tensor network.
+///
+/// Allows to select the optimizer at runtime, e.g. from a configuration.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::network::TensorNetwork;
+/// # use rustengra::optimizer::{NativeGreedy, NativeGreedyReconf, Optimizer};
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2]],
+///     &[0],
+///     &FxHashMap::from_iter([(0, 2), (1, 10), (2, 3)]),
+/// )
+/// .unwrap();
+///
+/// let use_reconf = true;
+/// let optimizer: Box<dyn Optimizer> = if use_reconf {
+///     Box::new(NativeGreedyReconf::default())
+/// } else {
+///     Box::new(NativeGreedy::default())
+/// };
+/// let path = optimizer.optimize(&network).unwrap();
+/// assert_eq!(path.len(), 2);
+/// ```
+pub trait Optimizer {
+    /// Finds a contraction path for `network`.
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath>;
+
+    /// Wraps this optimizer with `cache`, see [`CachedOptimizer`].
+    fn cached(self, cache: PathCache, refine: bool) -> CachedOptimizer<Self>
+    where
+        Self: Sized,
+    {
+        CachedOptimizer {
+            optimizer: self,
+            cache,
+            refine,
+        }
+    }
+}
+
+impl<O: Optimizer + ?Sized> Optimizer for Box<O> {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        (**self).optimize(network)
+    }
+}
+
+/// The native greedy optimizer, see [`greedy_path`].
+#[derive(Debug, Clone, Default)]
+pub struct NativeGreedy {
+    pub options: GreedyOptions,
+}
+
+impl Optimizer for NativeGreedy {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        Ok(greedy_path(network, &self.options))
+    }
+}
+
+/// The native greedy optimizer followed by native subtree reconfiguration, see
+/// [`greedy_path`] and [`subtree_reconfigure`].
+#[derive(Debug, Clone, Default)]
+pub struct NativeGreedyReconf {
+    pub greedy: GreedyOptions,
+    pub reconf: ReconfOptions,
+}
+
+impl Optimizer for NativeGreedyReconf {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        let path = greedy_path(network, &self.greedy);
+        let tree = ContractionTree::from_ssa_path(network, &path)?;
+        Ok(subtree_reconfigure(&tree, &self.reconf).ssa_path())
+    }
+}
+
+/// Subtree reconfiguration of a given path via cotengra, see
+/// [`cotengra_optimize_from_path`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+pub struct CotengraFromPath {
+    pub path: Vec<(usize, usize)>,
+    pub is_ssa: bool,
+    pub options: ReconfOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraFromPath {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_optimize_from_path(
+            network,
+            self.path.clone(),
+            &self.options,
+            self.is_ssa,
+            self.validate,
+            None,
+        )
+        .map(|result| result.ssa_path)
+    }
+}
+
+/// Forested subtree reconfiguration of a given path via cotengra, see
+/// [`cotengra_reconfigure_forest`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+pub struct CotengraForest {
+    pub path: Vec<(usize, usize)>,
+    pub is_ssa: bool,
+    pub options: ForestOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraForest {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_reconfigure_forest(
+            network,
+            self.path.clone(),
+            &self.options,
+            self.is_ssa,
+            self.validate,
+            None,
+        )
+        .map(|result| result.ssa_path)
+    }
+}
+
+/// The greedy optimizer of cotengra followed by subtree reconfiguration, see
+/// [`cotengra_optimized_greedy`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+pub struct CotengraGreedy {
+    pub options: ReconfOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraGreedy {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_optimized_greedy(network, &self.options, self.validate, None)
+            .map(|result| result.ssa_path)
+    }
+}
+
+/// Simulated annealing of an initial tree via cotengra, see [`cotengra_sa_tree`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Default)]
+pub struct CotengraAnnealing {
+    pub initial: InitialTree,
+    pub options: AnnealingOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraAnnealing {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_sa_tree(network, &self.initial, &self.options, self.validate, None)
+            .map(|result| result.ssa_path)
+    }
+}
+
+/// Parallel tempering of an initial tree via cotengra, see
+/// [`cotengra_tree_tempering`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone, Default)]
+pub struct CotengraTempering {
+    pub initial: InitialTree,
+    pub options: TemperingOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraTempering {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_tree_tempering(network, &self.initial, &self.options, self.validate, None)
+            .map(|result| result.ssa_path)
+    }
+}
+
+/// The Hyperoptimizer of cotengra, see [`cotengra_hyperoptimizer`].
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+pub struct CotengraHyper {
+    pub methods: Vec<HyperMethod>,
+    pub options: HyperOptions,
+    pub validate: bool,
+}
+
+#[cfg(feature = "python")]
+impl Optimizer for CotengraHyper {
+    fn optimize(&self, network: &TensorNetwork) -> Result<ContractionPath> {
+        cotengra_hyperoptimizer(network, &self.methods, &self.options, self.validate, None)
+            .map(|result| result.result.ssa_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+    use crate::utils::validate_ssa_path;
+
+    #[test]
+    fn test_native_optimizers() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0], vec![4]],
+            &[4],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+        )
+        .unwrap();
+
+        let optimizers: Vec<Box<dyn Optimizer>> = vec![
+            Box::new(NativeGreedy::default()),
+            Box::new(NativeGreedyReconf {
+                greedy: GreedyOptions::new().with_temperature(0.5).with_seed(1),
+                reconf: ReconfOptions::new().with_subtree_size(4),
+            }),
+        ];
+        for optimizer in optimizers {
+            let path = optimizer.optimize(&network).unwrap();
+            assert_eq!(validate_ssa_path(&path, &network), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_optimization_result() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3]],
+            &[0, 3],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5)]),
+        )
+        .unwrap();
+        let result = OptimizationResult::new(
+            &network,
+            vec![(0, 1), (3, 2)],
+            "test",
+            Some(1),
+            Duration::ZERO,
+        );
+        assert_eq!(result.replace_path, vec![(0, 1), (0, 2)]);
+        assert_eq!(result.log10_flops, 64f64.log10());
+        assert_eq!(result.max_size, 10.0);
+        assert_eq!(result.total_write, 18.0);
+
+        let result =
+            OptimizationResult::new(&network, vec![(0, 1), (1, 2)], "test", None, Duration::ZERO);
+        assert!(result.log10_flops.is_nan());
+
+        let single = TensorNetwork::from_usize_legs(
+            &[vec![0, 1]],
+            &[0, 1],
+            &FxHashMap::from_iter([(0, 2), (1, 3)]),
+        )
+        .unwrap();
+        let result = OptimizationResult::new(&single, vec![], "test", None, Duration::ZERO);
+        assert!(result.replace_path.is_empty());
+        assert_eq!(result.log10_flops, f64::NEG_INFINITY);
+        assert_eq!(result.total_write, 0.0);
+    }
+}
@@ -0,0 +1,733 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyDict};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::cost::Minimize;
+use crate::network::Legs;
+use crate::tree::ContractionTree;
+
+/// The largest `subtree_size` of the native [`subtree_reconfigure`]. Its exhaustive
+/// search takes time exponential in the subtree size, so larger sizes are clamped
+/// to this value.
+pub const MAX_NATIVE_SUBTREE_SIZE: usize = 16;
+
+/// How the nodes to reconfigure next are selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubtreeSelect {
+    /// The node with the largest weight.
+    #[default]
+    Max,
+    /// The node with the smallest weight.
+    Min,
+    /// A random node, where nodes of larger weight are more likely.
+    Random,
+}
+
+impl SubtreeSelect {
+    /// The corresponding string argument of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Max => "max",
+            Self::Min => "min",
+            Self::Random => "random",
+        }
+    }
+}
+
+/// The quantity of a node that determines its weight for selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubtreeWeight {
+    /// The flops of the contraction creating the node.
+    #[default]
+    Flops,
+    /// The size of the tensor of the node.
+    Size,
+}
+
+impl SubtreeWeight {
+    /// The corresponding string argument of cotengra.
+    pub fn as_cotengra_str(&self) -> &'static str {
+        match self {
+            Self::Flops => "flops",
+            Self::Size => "size",
+        }
+    }
+}
+
+/// The options for subtree reconfiguration.
+///
+/// The options mirror the ones of cotengra's `ContractionTree.subtree_reconfigure`.
+/// Unassigned options will not be passed to cotengra and hence the Python default
+/// values will be used. The native [`subtree_reconfigure`] uses the same defaults.
+/// Please see the cotengra documentation for details on the parameters.
+#[derive(Debug, Clone, Default)]
+pub struct ReconfOptions {
+    subtree_size: Option<usize>,
+    maxiter: Option<usize>,
+    minimize: Option<Minimize>,
+    seed: Option<u64>,
+    select: Option<SubtreeSelect>,
+    weight_what: Option<SubtreeWeight>,
+    weight_pwr: Option<f64>,
+    progbar: Option<bool>,
+}
+
+impl ReconfOptions {
+    /// Creates the default reconfiguration options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `subtree_size` argument, the number of leaves of the subtrees that
+    /// are optimized exhaustively. Defaults to `8`. The native
+    /// [`subtree_reconfigure`] uses at most [`MAX_NATIVE_SUBTREE_SIZE`].
+    pub fn with_subtree_size(mut self, subtree_size: usize) -> Self {
+        self.subtree_size = Some(subtree_size);
+        self
+    }
+
+    /// Sets the `maxiter` argument, the maximum number of subtrees to reconfigure.
+    /// Defaults to `500`.
+    pub fn with_maxiter(mut self, maxiter: usize) -> Self {
+        self.maxiter = Some(maxiter);
+        self
+    }
+
+    /// Sets the `minimize` argument. Defaults to [`Minimize::Flops`].
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `seed` argument, used to break ties between subtrees of equal
+    /// weight and for random selection.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the `select` argument, which node to reconfigure next. Defaults to
+    /// [`SubtreeSelect::Max`].
+    pub fn with_select(mut self, select: SubtreeSelect) -> Self {
+        self.select = Some(select);
+        self
+    }
+
+    /// Sets the `weight_what` argument, the quantity that weights the nodes for
+    /// selection. Defaults to [`SubtreeWeight::Flops`].
+    pub fn with_weight_what(mut self, weight_what: SubtreeWeight) -> Self {
+        self.weight_what = Some(weight_what);
+        self
+    }
+
+    /// Sets the `weight_pwr` argument, the power the weight is raised to. Only
+    /// affects [`SubtreeSelect::Random`]. Defaults to `2.0`.
+    pub fn with_weight_pwr(mut self, weight_pwr: f64) -> Self {
+        self.weight_pwr = Some(weight_pwr);
+        self
+    }
+
+    /// Sets the `progbar` argument. Only used by cotengra. Defaults to `false`.
+    pub fn with_progbar(mut self, progbar: bool) -> Self {
+        self.progbar = Some(progbar);
+        self
+    }
+
+    fn native_subtree_size(&self) -> usize {
+        self.subtree_size.unwrap_or(8)
+    }
+
+    fn native_maxiter(&self) -> usize {
+        self.maxiter.unwrap_or(500)
+    }
+
+    fn native_weight_pwr(&self) -> f64 {
+        self.weight_pwr.unwrap_or(2.0)
+    }
+}
+
+#[cfg(feature = "python")]
+impl ReconfOptions {
+    /// The seed, if assigned.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        if let Some(subtree_size) = self.subtree_size {
+            kwargs.set_item("subtree_size", subtree_size)?;
+        }
+        if let Some(maxiter) = self.maxiter {
+            kwargs.set_item("maxiter", maxiter)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(seed) = self.seed {
+            kwargs.set_item("seed", seed)?;
+        }
+        if let Some(select) = self.select {
+            kwargs.set_item("select", select.as_cotengra_str())?;
+        }
+        if let Some(weight_what) = self.weight_what {
+            kwargs.set_item("weight_what", weight_what.as_cotengra_str())?;
+        }
+        if let Some(weight_pwr) = self.weight_pwr {
+            kwargs.set_item("weight_pwr", weight_pwr)?;
+        }
+        if let Some(progbar) = self.progbar {
+            kwargs.set_item("progbar", progbar)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+/// The options for forested subtree reconfiguration, corresponding to cotengra's
+/// `ContractionTree.subtree_reconfigure_forest`.
+///
+/// Unassigned options will not be passed to cotengra and hence the Python default
+/// values will be used. Please see the cotengra documentation for details on the
+/// parameters.
+#[derive(Debug, Clone, Default)]
+pub struct ForestOptions {
+    num_trees: Option<usize>,
+    num_restarts: Option<usize>,
+    restart_fraction: Option<f64>,
+    parallel: Option<bool>,
+    minimize: Option<Minimize>,
+    seed: Option<u64>,
+    progbar: Option<bool>,
+}
+
+impl ForestOptions {
+    /// Creates the default forest options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `num_trees` argument, the number of trees reconfigured in parallel.
+    pub fn with_num_trees(mut self, num_trees: usize) -> Self {
+        self.num_trees = Some(num_trees);
+        self
+    }
+
+    /// Sets the `num_restarts` argument, the number of times the forest is
+    /// resampled.
+    pub fn with_num_restarts(mut self, num_restarts: usize) -> Self {
+        self.num_restarts = Some(num_restarts);
+        self
+    }
+
+    /// Sets the `restart_fraction` argument, the fraction of the best trees kept on
+    /// each restart.
+    pub fn with_restart_fraction(mut self, restart_fraction: f64) -> Self {
+        self.restart_fraction = Some(restart_fraction);
+        self
+    }
+
+    /// Sets the `parallel` argument.
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = Some(parallel);
+        self
+    }
+
+    /// Sets the `minimize` argument.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `seed` argument.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the `progbar` argument.
+    pub fn with_progbar(mut self, progbar: bool) -> Self {
+        self.progbar = Some(progbar);
+        self
+    }
+}
+
+#[cfg(feature = "python")]
+impl ForestOptions {
+    /// The seed, if assigned.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        if let Some(num_trees) = self.num_trees {
+            kwargs.set_item("num_trees", num_trees)?;
+        }
+        if let Some(num_restarts) = self.num_restarts {
+            kwargs.set_item("num_restarts", num_restarts)?;
+        }
+        if let Some(restart_fraction) = self.restart_fraction {
+            kwargs.set_item("restart_fraction", restart_fraction)?;
+        }
+        if let Some(parallel) = self.parallel {
+            kwargs.set_item("parallel", parallel)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(seed) = self.seed {
+            kwargs.set_item("seed", seed)?;
+        }
+        if let Some(progbar) = self.progbar {
+            kwargs.set_item("progbar", progbar)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+/// The score of a part of a contraction tree: the minimized quantity, with the
+/// flops as tie breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64, f64);
+
+impl Score {
+    fn is_better_than(&self, other: &Self) -> bool {
+        const EPS: f64 = 1e-12;
+        self.0 < other.0 * (1.0 - EPS)
+            || (self.0 <= other.0 * (1.0 + EPS) && self.1 < other.1 * (1.0 - EPS))
+    }
+}
+
+/// A node waiting for reconfiguration, ordered by weight.
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    weight: f64,
+    node: usize,
+    version: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight
+            .total_cmp(&other.weight)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The mutable structure of a contraction tree during reconfiguration. Node ids of
+/// replaced subtrees are reused, so the roots keep their ids.
+struct Reconfigurer<'a> {
+    tree: &'a ContractionTree,
+    minimize: Minimize,
+    legs: Vec<Legs>,
+    children: Vec<Option<(usize, usize)>>,
+    versions: Vec<usize>,
+}
+
+impl<'a> Reconfigurer<'a> {
+    fn new(tree: &'a ContractionTree, minimize: Minimize) -> Self {
+        Self {
+            tree,
+            minimize,
+            legs: (0..tree.num_nodes())
+                .map(|node| tree.node_legs(node).clone())
+                .collect(),
+            children: (0..tree.num_nodes())
+                .map(|node| tree.children(node))
+                .collect(),
+            versions: vec![0; tree.num_nodes()],
+        }
+    }
+
+    /// The flops and the result size of the contraction creating `node`.
+    fn contraction(&self, node: usize) -> (f64, f64) {
+        let (left, right) = self.children[node].unwrap();
+        let network = self.tree.indexed();
+        let (_, flops) = network.contract(&self.legs[left], &self.legs[right]);
+        (flops, network.size(&self.legs[node]))
+    }
+
+    /// Selects the subtree below `node` with up to `subtree_size` leaves, but at most
+    /// [`MAX_NATIVE_SUBTREE_SIZE`], by repeatedly expanding the largest leaf. Returns
+    /// its leaves and inner nodes.
+    fn select_subtree(&self, node: usize, subtree_size: usize) -> (Vec<usize>, Vec<usize>) {
+        let subtree_size = subtree_size.min(MAX_NATIVE_SUBTREE_SIZE);
+        let network = self.tree.indexed();
+        let (left, right) = self.children[node].unwrap();
+        let mut leaves = vec![left, right];
+        let mut inner = vec![node];
+        while leaves.len() < subtree_size {
+            let Some(position) = (0..leaves.len())
+                .filter(|position| self.children[leaves[*position]].is_some())
+                .max_by(|a, b| {
+                    let size_a = network.size(&self.legs[leaves[*a]]);
+                    let size_b = network.size(&self.legs[leaves[*b]]);
+                    size_a.total_cmp(&size_b)
+                })
+            else {
+                break;
+            };
+            let expanded = leaves.swap_remove(position);
+            let (left, right) = self.children[expanded].unwrap();
+            leaves.extend([left, right]);
+            inner.push(expanded);
+        }
+        (leaves, inner)
+    }
+
+    /// Finds the optimal contraction of `leaves` by dynamic programming over all
+    /// subsets. Returns the legs, the score and the best split of each subset.
+    fn optimal_subtree(&self, leaves: &[usize]) -> (Vec<Legs>, Vec<Score>, Vec<usize>) {
+        let network = self.tree.indexed();
+        let subsets = 1usize << leaves.len();
+        let mut legs = vec![Legs::new(); subsets];
+        let mut scores = vec![Score(0.0, 0.0); subsets];
+        let mut splits = vec![0; subsets];
+        for (position, leaf) in leaves.iter().enumerate() {
+            legs[1 << position] = self.legs[*leaf].clone();
+        }
+
+        for mask in 1..subsets {
+            if mask.is_power_of_two() {
+                continue;
+            }
+            let lowest = mask & mask.wrapping_neg();
+            legs[mask] = network.contract(&legs[lowest], &legs[mask ^ lowest]).0;
+            let size = network.size(&legs[mask]);
+
+            let mut best = Score(f64::INFINITY, f64::INFINITY);
+            // Enumerate all splits where the first part contains the lowest leaf
+            let mut left = (mask - 1) & mask;
+            while left > 0 {
+                if left & lowest != 0 {
+                    let right = mask ^ left;
+                    let (_, flops) = network.contract(&legs[left], &legs[right]);
+                    let score = self.minimize.contraction_score(flops, size);
+                    let score = Score(
+                        self.minimize.combine(
+                            self.minimize.combine(scores[left].0, scores[right].0),
+                            score,
+                        ),
+                        scores[left].1 + scores[right].1 + flops,
+                    );
+                    if score.is_better_than(&best) {
+                        best = score;
+                        splits[mask] = left;
+                    }
+                }
+                left = (left - 1) & mask;
+            }
+            scores[mask] = best;
+        }
+        (legs, scores, splits)
+    }
+
+    /// Reconfigures the subtree below `node`. Returns the nodes that changed.
+    fn reconfigure(&mut self, node: usize, subtree_size: usize) -> Vec<usize> {
+        let (leaves, mut inner) = self.select_subtree(node, subtree_size);
+        if leaves.len() < 3 {
+            return Vec::new();
+        }
+
+        let current = inner.iter().fold(Score(0.0, 0.0), |score, inner| {
+            let (flops, size) = self.contraction(*inner);
+            let node_score = self.minimize.contraction_score(flops, size);
+            Score(self.minimize.combine(score.0, node_score), score.1 + flops)
+        });
+        let (legs, scores, splits) = self.optimal_subtree(&leaves);
+        let full = legs.len() - 1;
+        if !scores[full].is_better_than(&current) {
+            return Vec::new();
+        }
+
+        // Rebuild the subtree, reusing the inner node ids with `node` as root
+        inner.retain(|inner| *inner != node);
+        let mut changed = Vec::new();
+        let mut stack = vec![(full, node)];
+        while let Some((mask, id)) = stack.pop() {
+            let split = splits[mask];
+            let mut child = |mask: usize| {
+                if mask.is_power_of_two() {
+                    leaves[mask.trailing_zeros() as usize]
+                } else {
+                    let id = inner.pop().unwrap();
+                    stack.push((mask, id));
+                    id
+                }
+            };
+            let left = child(split);
+            let right = child(mask ^ split);
+            self.children[id] = Some((left, right));
+            self.legs[id] = legs[mask].clone();
+            self.versions[id] += 1;
+            changed.push(id);
+        }
+        changed
+    }
+
+    /// The SSA path of the current tree.
+    fn ssa_path(&self) -> Vec<(usize, usize)> {
+        let mut ids = (0..self.children.len()).collect::<Vec<_>>();
+        let mut next_id = self.tree.num_leaves();
+        let mut path = Vec::new();
+        for root in self.tree.roots() {
+            let mut stack = vec![(root, false)];
+            while let Some((node, expanded)) = stack.pop() {
+                match self.children[node] {
+                    Some((left, right)) if !expanded => {
+                        stack.extend([(node, true), (right, false), (left, false)]);
+                    }
+                    Some((left, right)) => {
+                        path.push((ids[left], ids[right]));
+                        ids[node] = next_id;
+                        next_id += 1;
+                    }
+                    None => {}
+                }
+            }
+        }
+        path
+    }
+}
+
+/// Optimizes `tree` by subtree reconfiguration, without calling into Python.
+///
+/// Repeatedly selects an inner node according to `select` and `weight_what`, by
+/// default the one with the most expensive contraction, takes the subtree below it
+/// with up to `subtree_size` leaves and replaces it by the optimal contraction of
+/// these leaves, found by exhaustive search. Stops when no subtree can be improved
+/// or after `maxiter` subtrees. Subtree sizes above [`MAX_NATIVE_SUBTREE_SIZE`] are
+/// clamped.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::network::TensorNetwork;
+/// # use rustengra::reconf::{subtree_reconfigure, ReconfOptions};
+/// # use rustengra::tree::ContractionTree;
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3]],
+///     &[0],
+///     &FxHashMap::from_iter([(0, 10), (1, 10), (2, 10), (3, 10)]),
+/// )
+/// .unwrap();
+/// let tree = ContractionTree::from_ssa_path(&network, &[(0, 1), (4, 2), (5, 3)]).unwrap();
+/// let optimized = subtree_reconfigure(&tree, &ReconfOptions::default());
+/// assert!(optimized.cost().flops < tree.cost().flops);
+/// ```
+pub fn subtree_reconfigure(tree: &ContractionTree, options: &ReconfOptions) -> ContractionTree {
+    let select = options.select.unwrap_or_default();
+    let mut rng = match (options.seed, select) {
+        (Some(seed), _) => Some(StdRng::seed_from_u64(seed)),
+        (None, SubtreeSelect::Random) => Some(StdRng::from_os_rng()),
+        (None, _) => None,
+    };
+    let mut reconfigurer = Reconfigurer::new(tree, options.minimize.unwrap_or_default());
+
+    let mut queue = BinaryHeap::new();
+    let mut push = |queue: &mut BinaryHeap<_>, reconfigurer: &Reconfigurer, node: usize| {
+        let (flops, size) = reconfigurer.contraction(node);
+        let weight = match options.weight_what.unwrap_or_default() {
+            SubtreeWeight::Flops => flops,
+            SubtreeWeight::Size => size,
+        };
+        let jitter = rng
+            .as_mut()
+            .map_or(1.0, |rng| 1.0 + 1e-9 * rng.random::<f64>());
+        let weight = match select {
+            SubtreeSelect::Max => weight * jitter,
+            SubtreeSelect::Min => -weight * jitter,
+            SubtreeSelect::Random => {
+                let rng = rng.as_mut().unwrap();
+                weight.powf(options.native_weight_pwr()) * rng.random::<f64>()
+            }
+        };
+        queue.push(Candidate {
+            weight,
+            node,
+            version: reconfigurer.versions[node],
+        });
+    };
+    for node in tree.num_leaves()..tree.num_nodes() {
+        push(&mut queue, &reconfigurer, node);
+    }
+
+    let mut iterations = 0;
+    while iterations < options.native_maxiter() {
+        let Some(candidate) = queue.pop() else {
+            break;
+        };
+        if candidate.version != reconfigurer.versions[candidate.node] {
+            continue;
+        }
+        iterations += 1;
+        for node in reconfigurer.reconfigure(candidate.node, options.native_subtree_size()) {
+            push(&mut queue, &reconfigurer, node);
+        }
+    }
+
+    ContractionTree::from_ssa_path(tree.network(), &reconfigurer.ssa_path())
+        .expect("reconfigured path is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+    use crate::greedy::{greedy_path, GreedyOptions};
+    use crate::network::TensorNetwork;
+    use crate::utils::validate_ssa_path;
+
+    /// A 4x4 lattice with open boundary.
+    fn lattice() -> TensorNetwork {
+        let mut inputs = vec![Vec::new(); 16];
+        let mut next_leg = 0;
+        for row in 0..4 {
+            for column in 0..4 {
+                let tensor = 4 * row + column;
+                if column < 3 {
+                    inputs[tensor].push(next_leg);
+                    inputs[tensor + 1].push(next_leg);
+                    next_leg += 1;
+                }
+                if row < 3 {
+                    inputs[tensor].push(next_leg);
+                    inputs[tensor + 4].push(next_leg);
+                    next_leg += 1;
+                }
+            }
+        }
+        let size_dict = (0..next_leg)
+            .map(|leg| (leg, 2))
+            .collect::<FxHashMap<_, _>>();
+        TensorNetwork::from_usize_legs(&inputs, &[], &size_dict).unwrap()
+    }
+
+    #[test]
+    fn test_improves_linear_path() {
+        let network = lattice();
+        let mut ssa_path = vec![(0, 1)];
+        for tensor in 2..16 {
+            ssa_path.push((tensor + 14, tensor));
+        }
+        let tree = ContractionTree::from_ssa_path(&network, &ssa_path).unwrap();
+
+        for minimize in [Minimize::Flops, Minimize::Size, Minimize::Combo(64.0)] {
+            let options = ReconfOptions::new().with_minimize(minimize).with_seed(0);
+            let optimized = subtree_reconfigure(&tree, &options);
+            assert_eq!(validate_ssa_path(&optimized.ssa_path(), &network), Ok(()));
+            assert!(optimized.cost().flops <= tree.cost().flops);
+        }
+
+        let optimized = subtree_reconfigure(&tree, &ReconfOptions::default());
+        assert!(optimized.cost().flops < tree.cost().flops);
+    }
+
+    #[test]
+    fn test_never_worse_than_greedy() {
+        let network = lattice();
+        let path = greedy_path(&network, &GreedyOptions::default());
+        let tree = ContractionTree::from_ssa_path(&network, &path).unwrap();
+        let options = ReconfOptions::new().with_subtree_size(6).with_maxiter(100);
+        let optimized = subtree_reconfigure(&tree, &options);
+        assert_eq!(validate_ssa_path(&optimized.ssa_path(), &network), Ok(()));
+        assert!(optimized.cost().flops <= tree.cost().flops);
+    }
+
+    #[test]
+    fn test_subtree_size_is_clamped() {
+        let legs = (0..70)
+            .map(|tensor| vec![tensor, (tensor + 1) % 70])
+            .collect::<Vec<_>>();
+        let sizes = FxHashMap::from_iter((0..70).map(|leg| (leg, 2)));
+        let network = TensorNetwork::from_usize_legs(&legs, &[], &sizes).unwrap();
+        let ssa_path = (0..69)
+            .map(|step| {
+                if step == 0 {
+                    (0, 1)
+                } else {
+                    (69 + step, step + 1)
+                }
+            })
+            .collect::<Vec<_>>();
+        let tree = ContractionTree::from_ssa_path(&network, &ssa_path).unwrap();
+        let reconfigurer = Reconfigurer::new(&tree, Minimize::Flops);
+        let (leaves, inner) = reconfigurer.select_subtree(tree.root().unwrap(), 65);
+        assert_eq!(leaves.len(), MAX_NATIVE_SUBTREE_SIZE);
+        assert_eq!(inner.len(), MAX_NATIVE_SUBTREE_SIZE - 1);
+    }
+
+    #[test]
+    fn test_select() {
+        let network = lattice();
+        let path = greedy_path(&network, &GreedyOptions::default());
+        let tree = ContractionTree::from_ssa_path(&network, &path).unwrap();
+        for (select, weight_what) in [
+            (SubtreeSelect::Min, SubtreeWeight::Flops),
+            (SubtreeSelect::Max, SubtreeWeight::Size),
+            (SubtreeSelect::Random, SubtreeWeight::Flops),
+        ] {
+            let options = ReconfOptions::new()
+                .with_select(select)
+                .with_weight_what(weight_what)
+                .with_seed(3);
+            let optimized = subtree_reconfigure(&tree, &options);
+            assert_eq!(validate_ssa_path(&optimized.ssa_path(), &network), Ok(()));
+            assert!(optimized.cost().flops <= tree.cost().flops);
+            assert_eq!(
+                optimized.ssa_path(),
+                subtree_reconfigure(&tree, &options).ssa_path()
+            );
+        }
+    }
+
+    #[test]
+    fn test_forest() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2], vec![3], vec![3]],
+            &[0],
+            &FxHashMap::from_iter([(0, 10), (1, 10), (2, 10), (3, 10)]),
+        )
+        .unwrap();
+        let tree = ContractionTree::from_ssa_path(&network, &[(0, 1), (5, 2), (3, 4)]).unwrap();
+        let optimized = subtree_reconfigure(&tree, &ReconfOptions::default());
+        assert_eq!(optimized.ssa_path(), vec![(2, 1), (5, 0), (3, 4)]);
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_kwargs_only_assigned() {
+        Python::initialize();
+        Python::attach(|py| {
+            assert!(ReconfOptions::new().to_kwargs(py).unwrap().is_empty());
+
+            let options = ReconfOptions::new().with_subtree_size(6);
+            let kwargs = options.to_kwargs(py).unwrap();
+            assert_eq!(kwargs.len(), 1);
+            let subtree_size: usize = kwargs
+                .get_item("subtree_size")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(subtree_size, 6);
+            // Cotengra fills in `minimize` of nested options only if it is missing
+            assert!(kwargs.get_item("minimize").unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,402 @@
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyDict};
+
+use crate::cost::{contraction_cost, ContractionCost, Minimize};
+#[cfg(feature = "python")]
+use crate::cotengra_tree_from_path;
+use crate::network::{NetworkError, TensorNetwork};
+use crate::optimizer::OptimizationResult;
+use crate::reconf::ReconfOptions;
+#[cfg(feature = "python")]
+use crate::tree::ContractionTree;
+use crate::utils::validate_ssa_path;
+use crate::Result;
+
+/// The options for finding legs to slice, corresponding to cotengra's
+/// `SliceFinder`.
+///
+/// Unassigned options will not be passed to cotengra and hence the Python default
+/// values will be used. Please see the cotengra documentation for details on the
+/// parameters.
+#[derive(Debug, Clone, Default)]
+pub struct SlicingOptions {
+    target_size: Option<u64>,
+    target_overhead: Option<f64>,
+    target_slices: Option<u64>,
+    temperature: Option<f64>,
+    minimize: Option<Minimize>,
+    allow_outer: Option<bool>,
+    max_repeats: Option<usize>,
+    seed: Option<u64>,
+}
+
+impl SlicingOptions {
+    /// Creates the default slicing options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `target_size` argument, the maximum size of any intermediate tensor.
+    pub fn with_target_size(mut self, target_size: u64) -> Self {
+        self.target_size = Some(target_size);
+        self
+    }
+
+    /// Sets the `target_overhead` argument, the maximum ratio of the total cost of
+    /// all slices to the cost of the unsliced contraction.
+    pub fn with_target_overhead(mut self, target_overhead: f64) -> Self {
+        self.target_overhead = Some(target_overhead);
+        self
+    }
+
+    /// Sets the `target_slices` argument, the minimum number of slices.
+    pub fn with_target_slices(mut self, target_slices: u64) -> Self {
+        self.target_slices = Some(target_slices);
+        self
+    }
+
+    /// Sets the `temperature` argument.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the `minimize` argument.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `allow_outer` argument, whether output legs may be sliced.
+    pub fn with_allow_outer(mut self, allow_outer: bool) -> Self {
+        self.allow_outer = Some(allow_outer);
+        self
+    }
+
+    /// Sets the `max_repeats` argument, the number of slicing attempts.
+    pub fn with_max_repeats(mut self, max_repeats: usize) -> Self {
+        self.max_repeats = Some(max_repeats);
+        self
+    }
+
+    /// Sets the `seed` argument.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+#[cfg(feature = "python")]
+impl SlicingOptions {
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        if let Some(target_size) = self.target_size {
+            kwargs.set_item("target_size", target_size)?;
+        }
+        if let Some(target_overhead) = self.target_overhead {
+            kwargs.set_item("target_overhead", target_overhead)?;
+        }
+        if let Some(target_slices) = self.target_slices {
+            kwargs.set_item("target_slices", target_slices)?;
+        }
+        if let Some(temperature) = self.temperature {
+            kwargs.set_item("temperature", temperature)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(allow_outer) = self.allow_outer {
+            kwargs.set_item("allow_outer", allow_outer)?;
+        }
+        if let Some(max_repeats) = self.max_repeats {
+            kwargs.set_item("max_repeats", max_repeats)?;
+        }
+        if let Some(seed) = self.seed {
+            kwargs.set_item("seed", seed)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+/// The options for alternately slicing and reconfiguring a contraction tree,
+/// corresponding to cotengra's `ContractionTree.slice_and_reconfigure`.
+///
+/// The `target_size` is required. Unassigned options will not be passed to cotengra
+/// and hence the Python default values will be used. Please see the cotengra
+/// documentation for details on the parameters.
+#[derive(Debug, Clone)]
+pub struct SliceReconfOptions {
+    target_size: u64,
+    step_size: Option<u64>,
+    temperature: Option<f64>,
+    minimize: Option<Minimize>,
+    max_repeats: Option<usize>,
+    reconf_opts: Option<ReconfOptions>,
+}
+
+impl SliceReconfOptions {
+    /// Creates the default slice and reconfigure options with the given
+    /// `target_size`, the maximum size of any intermediate tensor.
+    pub fn new(target_size: u64) -> Self {
+        Self {
+            target_size,
+            step_size: None,
+            temperature: None,
+            minimize: None,
+            max_repeats: None,
+            reconf_opts: None,
+        }
+    }
+
+    /// The `target_size` argument.
+    pub fn target_size(&self) -> u64 {
+        self.target_size
+    }
+
+    /// Sets the `step_size` argument, the factor by which the size is reduced in
+    /// each step.
+    pub fn with_step_size(mut self, step_size: u64) -> Self {
+        self.step_size = Some(step_size);
+        self
+    }
+
+    /// Sets the `temperature` argument.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the `minimize` argument.
+    pub fn with_minimize(mut self, minimize: Minimize) -> Self {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Sets the `max_repeats` argument.
+    pub fn with_max_repeats(mut self, max_repeats: usize) -> Self {
+        self.max_repeats = Some(max_repeats);
+        self
+    }
+
+    /// Sets the `reconf_opts` argument, the options of the reconfiguration steps.
+    pub fn with_reconf_opts(mut self, reconf_opts: ReconfOptions) -> Self {
+        self.reconf_opts = Some(reconf_opts);
+        self
+    }
+}
+
+#[cfg(feature = "python")]
+impl SliceReconfOptions {
+    /// The options as keyword arguments for cotengra.
+    pub(crate) fn to_kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("target_size", self.target_size)?;
+        if let Some(step_size) = self.step_size {
+            kwargs.set_item("step_size", step_size)?;
+        }
+        if let Some(temperature) = self.temperature {
+            kwargs.set_item("temperature", temperature)?;
+        }
+        if let Some(minimize) = self.minimize {
+            kwargs.set_item("minimize", minimize.as_cotengra_str())?;
+        }
+        if let Some(max_repeats) = self.max_repeats {
+            kwargs.set_item("max_repeats", max_repeats)?;
+        }
+        if let Some(reconf_opts) = &self.reconf_opts {
+            kwargs.set_item("reconf_opts", reconf_opts.to_kwargs(py)?)?;
+        }
+        Ok(kwargs)
+    }
+}
+
+/// A contraction path together with the legs sliced over.
+///
+/// Each slice fixes the sliced legs to one value, so every slice is a contraction
+/// of the network with these legs removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlicedContraction {
+    /// The sliced legs.
+    pub sliced_legs: Vec<String>,
+    /// The number of slices, i.e. the product of the sizes of the sliced legs.
+    pub num_slices: u64,
+    /// The cost of contracting a single slice.
+    pub slice_cost: ContractionCost,
+    /// The cost of contracting all slices. The flops and write are summed over the
+    /// slices, while the sizes are those of a single slice.
+    pub total_cost: ContractionCost,
+}
+
+impl SlicedContraction {
+    /// Computes the costs of contracting `network` along `ssa_path` with the legs
+    /// `sliced_legs` sliced over.
+    ///
+    /// Fails if `ssa_path` is not a valid SSA path of `network` or if a sliced leg
+    /// has no size in the network.
+    ///
+    /// # Example
+    /// ```
+    /// # use rustc_hash::FxHashMap;
+    /// # use rustengra::network::TensorNetwork;
+    /// # use rustengra::slicing::SlicedContraction;
+    /// let network = TensorNetwork::from_usize_legs(
+    ///     &[vec![0, 1], vec![1, 2], vec![2, 0]],
+    ///     &[],
+    ///     &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4)]),
+    /// )
+    /// .unwrap();
+    /// let sliced =
+    ///     SlicedContraction::new(&network, &[(0, 1), (3, 2)], vec![String::from("1")]).unwrap();
+    /// assert_eq!(sliced.num_slices, 3);
+    /// assert_eq!(sliced.slice_cost.max_size, 8.0);
+    /// assert_eq!(sliced.total_cost.flops, 3.0 * sliced.slice_cost.flops);
+    /// ```
+    pub fn new(
+        network: &TensorNetwork,
+        ssa_path: &[(usize, usize)],
+        sliced_legs: Vec<String>,
+    ) -> Result<Self> {
+        validate_ssa_path(ssa_path, network)?;
+        let mut size_dict = network.size_dict().clone();
+        let mut num_slices = 1u64;
+        for leg in &sliced_legs {
+            let size = size_dict
+                .get_mut(leg)
+                .ok_or_else(|| NetworkError::MissingSize(leg.clone()))?;
+            num_slices = num_slices.saturating_mul(*size);
+            *size = 1;
+        }
+        let sliced_network = TensorNetwork::new(
+            network.inputs().to_vec(),
+            network.outputs().to_vec(),
+            size_dict,
+        )?;
+
+        let slice_cost = contraction_cost(&sliced_network, ssa_path);
+        let total_cost = ContractionCost {
+            flops: slice_cost.flops * num_slices as f64,
+            write: slice_cost.write * num_slices as f64,
+            ..slice_cost
+        };
+        Ok(Self {
+            sliced_legs,
+            num_slices,
+            slice_cost,
+            total_cost,
+        })
+    }
+}
+
+/// The result of a combined slicing and reconfiguration, see
+/// `cotengra_slice_and_reconfigure`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliceReconfResult {
+    /// The reconfigured contraction path.
+    pub result: OptimizationResult,
+    /// The sliced legs and the costs of the sliced contraction.
+    pub sliced: SlicedContraction,
+}
+
+/// Finds legs to slice for the contraction of `network` along `ssa_path` via
+/// cotengra's `ContractionTree.slice`.
+///
+/// The targets, e.g. the maximum intermediate size, are set in `options`. Fails
+/// if `ssa_path` is not a valid SSA path of `network`. Returns the sliced legs
+/// together with the per-slice and total costs. See [`cotengra_slice_tree`] to
+/// slice a native [`ContractionTree`].
+#[cfg(feature = "python")]
+pub fn cotengra_slice(
+    network: &TensorNetwork,
+    ssa_path: &[(usize, usize)],
+    options: &SlicingOptions,
+) -> Result<SlicedContraction> {
+    validate_ssa_path(ssa_path, network)?;
+    Python::initialize();
+    let sliced_legs = Python::attach(|py| {
+        let tree = cotengra_tree_from_path(py, network, ssa_path.to_vec(), true)?.call_method(
+            "slice",
+            (),
+            Some(&options.to_kwargs(py)?),
+        )?;
+        sliced_legs(&tree)
+    })?;
+
+    SlicedContraction::new(network, ssa_path, sliced_legs)
+}
+
+/// Finds legs to slice for the contraction given by `tree`, like
+/// [`cotengra_slice`].
+#[cfg(feature = "python")]
+pub fn cotengra_slice_tree(
+    tree: &ContractionTree,
+    options: &SlicingOptions,
+) -> Result<SlicedContraction> {
+    cotengra_slice(tree.network(), &tree.ssa_path(), options)
+}
+
+/// The legs sliced over by the Python contraction tree `tree`.
+#[cfg(feature = "python")]
+pub(crate) fn sliced_legs(tree: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    // Depending on the cotengra version this is a dict or a set of legs
+    tree.getattr("sliced_inds")?
+        .try_iter()?
+        .map(|leg| leg?.extract())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+    use crate::utils::PathError;
+
+    #[test]
+    fn test_sliced_contraction() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0]],
+            &[],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5)]),
+        )
+        .unwrap();
+        let path = [(0, 1), (2, 3), (4, 5)];
+        let unsliced = contraction_cost(&network, &path);
+
+        let sliced =
+            SlicedContraction::new(&network, &path, vec![String::from("0"), String::from("2")])
+                .unwrap();
+        assert_eq!(sliced.num_slices, 8);
+        assert_eq!(sliced.slice_cost.max_size, 1.0);
+        assert_eq!(sliced.total_cost.flops, 8.0 * sliced.slice_cost.flops);
+        assert!(sliced.slice_cost.max_size < unsliced.max_size);
+
+        let unsliced_legs = SlicedContraction::new(&network, &path, vec![]).unwrap();
+        assert_eq!(unsliced_legs.total_cost, unsliced);
+
+        assert_eq!(
+            SlicedContraction::new(&network, &path, vec![String::from("9")]),
+            Err(NetworkError::MissingSize(String::from("9")).into())
+        );
+        assert_eq!(
+            SlicedContraction::new(&network, &[(0, 1), (0, 2)], vec![]),
+            Err(PathError::AlreadyContracted { step: 1, id: 0 }.into())
+        );
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_slice_invalid_path() {
+        let network = TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 0]],
+            &[],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4)]),
+        )
+        .unwrap();
+        let err = cotengra_slice(&network, &[(0, 1), (0, 2)], &SlicingOptions::new()).unwrap_err();
+        assert_eq!(
+            err,
+            crate::Error::InvalidPath(PathError::AlreadyContracted { step: 1, id: 0 })
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use crate::cost::{contraction_cost, ContractionCost};
+use crate::network::{IndexedNetwork, Legs, TensorNetwork};
+use crate::utils::{check_replace_steps, replace_to_ssa_path, ssa_to_replace_path, PathError};
+
+/// A contraction path in nested form, e.g. `((0, 1), (2, 3))`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NestedPath {
+    /// The input tensor with the given index.
+    Leaf(usize),
+    /// The contraction of two nested paths.
+    Node(Box<NestedPath>, Box<NestedPath>),
+}
+
+impl NestedPath {
+    /// Creates the contraction of `left` and `right`.
+    pub fn node(left: NestedPath, right: NestedPath) -> Self {
+        Self::Node(Box::new(left), Box::new(right))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    legs: Legs,
+    children: Option<(usize, usize)>,
+    parent: Option<usize>,
+    flops: f64,
+}
+
+/// A binary contraction tree of a [`TensorNetwork`].
+///
+/// Nodes are identified by their SSA id: the leaves `0..n` are the input tensors and
+/// every contraction creates the next larger id. Each node stores the legs of the
+/// tensor it represents. A path that does not contract the network down to a single
+/// tensor results in a forest with several roots.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::network::TensorNetwork;
+/// # use rustengra::tree::ContractionTree;
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0, 1], vec![1, 2], vec![2, 3]],
+///     &[0, 3],
+///     &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5)]),
+/// )
+/// .unwrap();
+/// let tree = ContractionTree::from_ssa_path(&network, &[(0, 1), (3, 2)]).unwrap();
+/// assert_eq!(tree.root(), Some(4));
+/// assert_eq!(tree.children(4), Some((3, 2)));
+/// assert_eq!(tree.legs(3), vec!["0", "2"]);
+/// assert_eq!(tree.depth(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContractionTree {
+    network: TensorNetwork,
+    indexed: IndexedNetwork,
+    nodes: Vec<Node>,
+}
+
+impl ContractionTree {
+    /// Creates the contraction tree of `network` given by the SSA path `ssa_path`.
+    ///
+    /// Fails if the path contracts a tensor with itself or a tensor that does not
+    /// exist or has already been contracted. The path does not need to be complete.
+    pub fn from_ssa_path(
+        network: &TensorNetwork,
+        ssa_path: &[(usize, usize)],
+    ) -> Result<Self, PathError> {
+        let indexed = network.indexed();
+        let mut nodes = (0..indexed.num_tensors())
+            .map(|tensor| Node {
+                legs: indexed.input_legs(tensor).clone(),
+                children: None,
+                parent: None,
+                flops: 0.0,
+            })
+            .collect::<Vec<_>>();
+
+        for (step, &(i, j)) in ssa_path.iter().enumerate() {
+            if i == j {
+                return Err(PathError::SelfContraction { step, id: i });
+            }
+            let id = nodes.len();
+            for child in [i, j] {
+                match nodes.get_mut(child) {
+                    None => return Err(PathError::OutOfBounds { step, id: child }),
+                    Some(node) if node.parent.is_some() => {
+                        return Err(PathError::AlreadyContracted { step, id: child })
+                    }
+                    Some(node) => node.parent = Some(id),
+                }
+            }
+
+            let (legs, flops) = indexed.contract(&nodes[i].legs, &nodes[j].legs);
+            nodes.push(Node {
+                legs,
+                children: Some((i, j)),
+                parent: None,
+                flops,
+            });
+        }
+
+        Ok(Self {
+            network: network.clone(),
+            indexed,
+            nodes,
+        })
+    }
+
+    /// Creates the contraction tree of `network` given by the replace left path
+    /// `replace_path`. See [`ContractionTree::from_ssa_path`] for details.
+    pub fn from_replace_path(
+        network: &TensorNetwork,
+        replace_path: &[(usize, usize)],
+    ) -> Result<Self, PathError> {
+        check_replace_steps(replace_path, network.num_tensors())?;
+        let ssa_path = replace_to_ssa_path(replace_path.to_vec(), network.num_tensors());
+        Self::from_ssa_path(network, &ssa_path)
+    }
+
+    /// Creates the contraction tree of `network` given by the nested path `nested`.
+    /// See [`ContractionTree::from_ssa_path`] for details. A leaf that appears twice
+    /// is reported as already contracted.
+    pub fn from_nested(network: &TensorNetwork, nested: &NestedPath) -> Result<Self, PathError> {
+        fn visit(
+            nested: &NestedPath,
+            num_tensors: usize,
+            ssa_path: &mut Vec<(usize, usize)>,
+        ) -> Result<usize, PathError> {
+            match nested {
+                // Leaf ids beyond the inputs would alias intermediate tensors
+                NestedPath::Leaf(tensor) if *tensor >= num_tensors => Err(PathError::OutOfBounds {
+                    step: ssa_path.len(),
+                    id: *tensor,
+                }),
+                NestedPath::Leaf(tensor) => Ok(*tensor),
+                NestedPath::Node(left, right) => {
+                    let left = visit(left, num_tensors, ssa_path)?;
+                    let right = visit(right, num_tensors, ssa_path)?;
+                    ssa_path.push((left, right));
+                    Ok(num_tensors + ssa_path.len() - 1)
+                }
+            }
+        }
+
+        let mut ssa_path = Vec::new();
+        visit(nested, network.num_tensors(), &mut ssa_path)?;
+        Self::from_ssa_path(network, &ssa_path)
+    }
+
+    /// The integer leg representation of the network.
+    pub(crate) fn indexed(&self) -> &IndexedNetwork {
+        &self.indexed
+    }
+
+    /// The legs of the tensor represented by `node`, as leg ids and counts.
+    pub(crate) fn node_legs(&self, node: usize) -> &Legs {
+        &self.nodes[node].legs
+    }
+
+    /// The tensor network this tree contracts.
+    pub fn network(&self) -> &TensorNetwork {
+        &self.network
+    }
+
+    /// The number of leaves, i.e. input tensors.
+    pub fn num_leaves(&self) -> usize {
+        self.indexed.num_tensors()
+    }
+
+    /// The number of nodes, including leaves.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether `node` is a leaf, i.e. an input tensor.
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.nodes[node].children.is_none()
+    }
+
+    /// The two nodes contracted to obtain `node` or `None` if `node` is a leaf.
+    pub fn children(&self, node: usize) -> Option<(usize, usize)> {
+        self.nodes[node].children
+    }
+
+    /// The node `node` is contracted into or `None` if `node` is a root.
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes[node].parent
+    }
+
+    /// All nodes without parent, in ascending order.
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|node| self.nodes[*node].parent.is_none())
+            .collect()
+    }
+
+    /// The root of the tree or `None` if the tree is a forest.
+    pub fn root(&self) -> Option<usize> {
+        match self.roots()[..] {
+            [root] => Some(root),
+            _ => None,
+        }
+    }
+
+    /// The names of the legs of the tensor represented by `node`.
+    pub fn legs(&self, node: usize) -> Vec<&str> {
+        self.nodes[node]
+            .legs
+            .iter()
+            .map(|(leg, _)| self.indexed.leg_name(*leg))
+            .collect()
+    }
+
+    /// The nodes of the subtree below `node` in post order, i.e. children before
+    /// their parents, ending with `node` itself.
+    pub fn postorder(&self, node: usize) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut stack = vec![(node, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            match self.nodes[node].children {
+                Some((left, right)) if !expanded => {
+                    stack.push((node, true));
+                    stack.push((right, false));
+                    stack.push((left, false));
+                }
+                _ => order.push(node),
+            }
+        }
+        order
+    }
+
+    /// The leaves of the subtree below `node`, in ascending order.
+    pub fn leaves(&self, node: usize) -> Vec<usize> {
+        let mut leaves = self
+            .postorder(node)
+            .into_iter()
+            .filter(|node| self.is_leaf(*node))
+            .collect::<Vec<_>>();
+        leaves.sort_unstable();
+        leaves
+    }
+
+    /// Extracts the subtree below `node` as a tree of its own.
+    ///
+    /// The leaves of the new tree are the leaves below `node` in ascending order and
+    /// its output legs are the legs of `node`. Also returns, for each leaf of the new
+    /// tree, the corresponding leaf of this tree.
+    pub fn subtree(&self, node: usize) -> (ContractionTree, Vec<usize>) {
+        let leaves = self.leaves(node);
+        let mut ids = vec![usize::MAX; self.nodes.len()];
+        for (new_id, leaf) in leaves.iter().enumerate() {
+            ids[*leaf] = new_id;
+        }
+
+        let mut next_id = leaves.len();
+        let mut ssa_path = Vec::new();
+        for node in self.postorder(node) {
+            if let Some((left, right)) = self.nodes[node].children {
+                ssa_path.push((ids[left], ids[right]));
+                ids[node] = next_id;
+                next_id += 1;
+            }
+        }
+
+        let inputs = leaves
+            .iter()
+            .map(|leaf| self.network.inputs()[*leaf].clone())
+            .collect();
+        let outputs = self.legs(node).into_iter().map(String::from).collect();
+        let network = TensorNetwork::new(inputs, outputs, self.network.size_dict().clone())
+            .expect("subtree of a valid network is valid");
+
+        let tree = Self::from_ssa_path(&network, &ssa_path).expect("subtree path is valid");
+        (tree, leaves)
+    }
+
+    /// The number of contractions on the longest path from a root to a leaf.
+    pub fn depth(&self) -> usize {
+        // Parents always have larger ids than their children
+        let mut depths = vec![0; self.nodes.len()];
+        for node in (0..self.nodes.len()).rev() {
+            if let Some((left, right)) = self.nodes[node].children {
+                depths[left] = depths[node] + 1;
+                depths[right] = depths[node] + 1;
+            }
+        }
+        depths.into_iter().max().unwrap_or(0)
+    }
+
+    /// The number of contractions between `node` and its root.
+    pub fn node_depth(&self, mut node: usize) -> usize {
+        let mut depth = 0;
+        while let Some(parent) = self.nodes[node].parent {
+            node = parent;
+            depth += 1;
+        }
+        depth
+    }
+
+    /// The number of elements of the tensor represented by `node`.
+    pub fn size(&self, node: usize) -> f64 {
+        self.indexed.size(&self.nodes[node].legs)
+    }
+
+    /// The flops of the contraction creating `node`, zero for leaves.
+    pub fn flops(&self, node: usize) -> f64 {
+        self.nodes[node].flops
+    }
+
+    /// The number of elements written by the contraction creating `node`, zero for
+    /// leaves.
+    pub fn write(&self, node: usize) -> f64 {
+        if self.is_leaf(node) {
+            0.0
+        } else {
+            self.size(node)
+        }
+    }
+
+    /// The total cost of contracting the tree.
+    pub fn cost(&self) -> ContractionCost {
+        contraction_cost(&self.network, &self.ssa_path())
+    }
+
+    /// The contraction path of the tree in SSA format.
+    pub fn ssa_path(&self) -> Vec<(usize, usize)> {
+        self.nodes.iter().filter_map(|node| node.children).collect()
+    }
+
+    /// The contraction path of the tree in replace left format.
+    pub fn replace_path(&self) -> Vec<(usize, usize)> {
+        ssa_to_replace_path(self.ssa_path(), self.num_leaves())
+    }
+
+    /// The subtree below `node` as nested path.
+    pub fn nested(&self, node: usize) -> NestedPath {
+        match self.nodes[node].children {
+            None => NestedPath::Leaf(node),
+            Some((left, right)) => NestedPath::node(self.nested(left), self.nested(right)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+
+    fn network() -> TensorNetwork {
+        TensorNetwork::from_usize_legs(
+            &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4]],
+            &[0, 4],
+            &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_path_formats() {
+        let network = network();
+        let ssa_path = vec![(0, 1), (2, 3), (4, 5)];
+
+        let tree = ContractionTree::from_ssa_path(&network, &ssa_path).unwrap();
+        assert_eq!(tree.ssa_path(), ssa_path);
+        assert_eq!(tree.replace_path(), vec![(0, 1), (2, 3), (0, 2)]);
+
+        let nested = NestedPath::node(
+            NestedPath::node(NestedPath::Leaf(0), NestedPath::Leaf(1)),
+            NestedPath::node(NestedPath::Leaf(2), NestedPath::Leaf(3)),
+        );
+        assert_eq!(tree.nested(6), nested);
+
+        let from_replace =
+            ContractionTree::from_replace_path(&network, &tree.replace_path()).unwrap();
+        assert_eq!(from_replace.ssa_path(), ssa_path);
+        let from_nested = ContractionTree::from_nested(&network, &nested).unwrap();
+        assert_eq!(from_nested.ssa_path(), ssa_path);
+    }
+
+    #[test]
+    fn test_structure() {
+        let network = network();
+        let tree = ContractionTree::from_ssa_path(&network, &[(0, 1), (4, 2), (5, 3)]).unwrap();
+
+        assert_eq!(tree.root(), Some(6));
+        assert_eq!(tree.parent(2), Some(5));
+        assert_eq!(tree.children(5), Some((4, 2)));
+        assert!(tree.is_leaf(3));
+        assert_eq!(tree.postorder(5), vec![0, 1, 4, 2, 5]);
+        assert_eq!(tree.leaves(5), vec![0, 1, 2]);
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.node_depth(1), 3);
+        assert_eq!(tree.node_depth(3), 1);
+        assert_eq!(tree.legs(5), vec!["0", "3"]);
+        assert_eq!(tree.size(5), 10.0);
+        assert_eq!(tree.flops(5), 40.0);
+        assert_eq!(tree.write(0), 0.0);
+
+        let cost = tree.cost();
+        let flops: f64 = (0..tree.num_nodes()).map(|node| tree.flops(node)).sum();
+        assert_eq!(cost.flops, flops);
+    }
+
+    #[test]
+    fn test_forest() {
+        let network = network();
+        let tree = ContractionTree::from_ssa_path(&network, &[(0, 1), (2, 3)]).unwrap();
+        assert_eq!(tree.roots(), vec![4, 5]);
+        assert_eq!(tree.root(), None);
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let network = network();
+        let err = ContractionTree::from_ssa_path(&network, &[(0, 1), (4, 0)]).unwrap_err();
+        assert_eq!(err, PathError::AlreadyContracted { step: 1, id: 0 });
+        let err = ContractionTree::from_ssa_path(&network, &[(0, 5)]).unwrap_err();
+        assert_eq!(err, PathError::OutOfBounds { step: 0, id: 5 });
+        let err = ContractionTree::from_replace_path(&network, &[(2, 2)]).unwrap_err();
+        assert_eq!(err, PathError::SelfContraction { step: 0, id: 2 });
+
+        let leaf = NestedPath::Leaf;
+        let duplicate = NestedPath::node(NestedPath::node(leaf(0), leaf(1)), leaf(0));
+        let err = ContractionTree::from_nested(&network, &duplicate).unwrap_err();
+        assert_eq!(err, PathError::AlreadyContracted { step: 1, id: 0 });
+        // Leaf 4 is not an input, even though an intermediate tensor has that id
+        let aliased = NestedPath::node(NestedPath::node(leaf(0), leaf(1)), leaf(4));
+        let err = ContractionTree::from_nested(&network, &aliased).unwrap_err();
+        assert_eq!(err, PathError::OutOfBounds { step: 1, id: 4 });
+    }
+
+    #[test]
+    fn test_subtree() {
+        let network = network();
+        let tree = ContractionTree::from_ssa_path(&network, &[(3, 2), (1, 4), (0, 5)]).unwrap();
+
+        let (subtree, leaves) = tree.subtree(5);
+        assert_eq!(leaves, vec![1, 2, 3]);
+        assert_eq!(subtree.ssa_path(), vec![(2, 1), (0, 3)]);
+        assert_eq!(subtree.network().outputs(), &["1", "4"]);
+        assert_eq!(subtree.size(4), tree.size(5));
+    }
+}
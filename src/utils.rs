@@ -1,7 +1,10 @@
+use std::fmt;
 use std::iter::zip;
 
 use rustc_hash::FxHashMap;
 
+use crate::network::TensorNetwork;
+
 /// Converts tensor leg inputs (as usize) to chars. Creates new inputs, outputs and size_dict that can be fed to Cotengra.
 pub fn tensor_legs_to_digit(
     inputs: &[Vec<usize>],
@@ -39,14 +42,12 @@ pub fn ssa_to_replace_path(
     mut ssa_path: Vec<(usize, usize)>,
     tensor_len: usize,
 ) -> Vec<(usize, usize)> {
-    let mut next_id = tensor_len;
     let mut id_update = FxHashMap::default();
-    for (i, j) in &mut ssa_path {
+    for (next_id, (i, j)) in (tensor_len..).zip(&mut ssa_path) {
         let left_id = *id_update.get(i).unwrap_or(i);
         let right_id = *id_update.get(j).unwrap_or(j);
 
         id_update.insert(next_id, left_id);
-        next_id += 1;
         *i = left_id;
         *j = right_id;
     }
@@ -67,20 +68,147 @@ pub fn replace_to_ssa_path(
     mut replace_path: Vec<(usize, usize)>,
     tensor_len: usize,
 ) -> Vec<(usize, usize)> {
-    let mut next_id = tensor_len;
     let mut id_update = FxHashMap::default();
-    for (i, j) in &mut replace_path {
+    for (next_id, (i, j)) in (tensor_len..).zip(&mut replace_path) {
         let left_id = *id_update.get(i).unwrap_or(i);
         let right_id = *id_update.get(j).unwrap_or(j);
 
         id_update.insert(*i, next_id);
-        next_id += 1;
         *i = left_id;
         *j = right_id;
     }
     replace_path
 }
 
+/// The reason a contraction path is invalid. Steps are counted from zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The tensor `id` contracted in step `step` does not exist (yet).
+    OutOfBounds { step: usize, id: usize },
+    /// The tensor `id` contracted in step `step` has already been contracted.
+    AlreadyContracted { step: usize, id: usize },
+    /// Step `step` contracts tensor `id` with itself.
+    SelfContraction { step: usize, id: usize },
+    /// The path leaves `remaining` tensors uncontracted.
+    Incomplete { remaining: usize },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { step, id } => {
+                write!(f, "step {step} contracts tensor {id}, which does not exist")
+            }
+            Self::AlreadyContracted { step, id } => {
+                write!(
+                    f,
+                    "step {step} contracts tensor {id}, which was already contracted"
+                )
+            }
+            Self::SelfContraction { step, id } => {
+                write!(f, "step {step} contracts tensor {id} with itself")
+            }
+            Self::Incomplete { remaining } => {
+                write!(f, "path leaves {remaining} tensors uncontracted")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Checks that `ssa_path` is a valid and complete SSA contraction path of `network`.
+///
+/// Every step must contract two distinct tensors that exist and have not been
+/// contracted before. The path must either contract the whole network to a single
+/// tensor or, for disconnected networks, contract each connected component to a
+/// single tensor without ever contracting two components with each other.
+///
+/// # Example
+/// ```
+/// # use rustc_hash::FxHashMap;
+/// # use rustengra::network::TensorNetwork;
+/// # use rustengra::utils::{validate_ssa_path, PathError};
+/// let network = TensorNetwork::from_usize_legs(
+///     &[vec![0], vec![0, 1], vec![1]],
+///     &[],
+///     &FxHashMap::from_iter([(0, 2), (1, 2)]),
+/// )
+/// .unwrap();
+/// assert_eq!(validate_ssa_path(&[(0, 1), (3, 2)], &network), Ok(()));
+/// assert_eq!(
+///     validate_ssa_path(&[(0, 1), (0, 2)], &network),
+///     Err(PathError::AlreadyContracted { step: 1, id: 0 })
+/// );
+/// ```
+pub fn validate_ssa_path(
+    ssa_path: &[(usize, usize)],
+    network: &TensorNetwork,
+) -> Result<(), PathError> {
+    let tensor_len = network.num_tensors();
+    let mut components = network.connected_components();
+    let num_components = components.iter().max().map_or(0, |max| max + 1);
+    let mut contracted = vec![false; tensor_len];
+    let mut joins_components = false;
+
+    for (step, (i, j)) in ssa_path.iter().copied().enumerate() {
+        if i == j {
+            return Err(PathError::SelfContraction { step, id: i });
+        }
+        for id in [i, j] {
+            match contracted.get(id) {
+                None => return Err(PathError::OutOfBounds { step, id }),
+                Some(true) => return Err(PathError::AlreadyContracted { step, id }),
+                Some(false) => contracted[id] = true,
+            }
+        }
+        joins_components |= components[i] != components[j];
+        components.push(components[i]);
+        contracted.push(false);
+    }
+
+    let remaining = tensor_len - ssa_path.len();
+    if remaining <= 1 || (remaining == num_components && !joins_components) {
+        Ok(())
+    } else {
+        Err(PathError::Incomplete { remaining })
+    }
+}
+
+/// Checks that `replace_path` is a valid and complete replace left contraction path
+/// of `network`. See [`validate_ssa_path`] for details.
+pub fn validate_replace_path(
+    replace_path: &[(usize, usize)],
+    network: &TensorNetwork,
+) -> Result<(), PathError> {
+    check_replace_steps(replace_path, network.num_tensors())?;
+    let ssa_path = replace_to_ssa_path(replace_path.to_vec(), network.num_tensors());
+    validate_ssa_path(&ssa_path, network)
+}
+
+/// Checks that every step of `replace_path` contracts two distinct tensors that
+/// exist, without checking that the path is complete.
+pub(crate) fn check_replace_steps(
+    replace_path: &[(usize, usize)],
+    tensor_len: usize,
+) -> Result<(), PathError> {
+    let mut contracted = vec![false; tensor_len];
+    for (step, (i, j)) in replace_path.iter().copied().enumerate() {
+        if i == j {
+            return Err(PathError::SelfContraction { step, id: i });
+        }
+        for id in [i, j] {
+            match contracted.get(id) {
+                None => return Err(PathError::OutOfBounds { step, id }),
+                Some(true) => return Err(PathError::AlreadyContracted { step, id }),
+                Some(false) => {}
+            }
+        }
+        contracted[j] = true;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +254,60 @@ mod tests {
             ])
         );
     }
+
+    fn disconnected_network() -> TensorNetwork {
+        TensorNetwork::from_usize_legs(
+            &[vec![0], vec![0, 1], vec![1], vec![2], vec![2]],
+            &[],
+            &FxHashMap::from_iter([(0, 2), (1, 2), (2, 2)]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_ssa_path() {
+        let network = disconnected_network();
+        assert_eq!(
+            validate_ssa_path(&[(0, 1), (5, 2), (3, 4), (6, 7)], &network),
+            Ok(())
+        );
+        // Each component contracted on its own
+        assert_eq!(
+            validate_ssa_path(&[(0, 1), (5, 2), (3, 4)], &network),
+            Ok(())
+        );
+        assert_eq!(
+            validate_ssa_path(&[(0, 1), (5, 3), (2, 4)], &network),
+            Err(PathError::Incomplete { remaining: 2 })
+        );
+        assert_eq!(
+            validate_ssa_path(&[(0, 1)], &network),
+            Err(PathError::Incomplete { remaining: 4 })
+        );
+        assert_eq!(
+            validate_ssa_path(&[(0, 1), (6, 2)], &network),
+            Err(PathError::OutOfBounds { step: 1, id: 6 })
+        );
+        assert_eq!(
+            validate_ssa_path(&[(0, 1), (2, 2)], &network),
+            Err(PathError::SelfContraction { step: 1, id: 2 })
+        );
+    }
+
+    #[test]
+    fn test_validate_replace_path() {
+        let network = disconnected_network();
+        assert_eq!(
+            validate_replace_path(&[(0, 1), (0, 2), (3, 4), (0, 3)], &network),
+            Ok(())
+        );
+        assert_eq!(
+            validate_replace_path(&[(0, 1), (1, 2)], &network),
+            Err(PathError::AlreadyContracted { step: 1, id: 1 })
+        );
+        assert_eq!(
+            validate_replace_path(&[(0, 5)], &network),
+            Err(PathError::OutOfBounds { step: 0, id: 5 })
+        );
+    }
 }
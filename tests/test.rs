@@ -1,15 +1,25 @@
+#![cfg(feature = "python")]
+
 use std::time::Duration;
 
 use rustc_hash::FxHashMap;
 use rustengra::{
-    cotengra_optimize_from_path, cotengra_optimized_greedy, cotengra_sa_tree,
-    cotengra_tree_tempering,
-    hyper::{cotengra_hyperoptimizer, HyperOptions},
+    annealing::{AnnealingOptions, InitialTree, TemperingOptions},
+    cache::PathCache,
+    cotengra_optimize_from_path, cotengra_optimized_greedy, cotengra_reconfigure_forest,
+    cotengra_sa_tree, cotengra_slice_and_reconfigure, cotengra_tree_tempering,
+    hyper::{cotengra_hyperoptimizer, HyperMethod, HyperOptions},
+    network::TensorNetwork,
+    optimizer::{CotengraAnnealing, CotengraGreedy, Optimizer},
+    reconf::{ForestOptions, ReconfOptions},
+    slicing::{cotengra_slice, cotengra_slice_tree, SliceReconfOptions, SlicingOptions},
+    tree::ContractionTree,
+    utils::{ssa_to_replace_path, validate_ssa_path},
 };
 
 #[test]
 fn integration_test() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("a"), String::from("8"), String::from("9")],
         vec![String::from("5"), String::from("1"), String::from("0")],
         vec![String::from("6"), String::from("8"), String::from("9")],
@@ -22,7 +32,7 @@ fn integration_test() {
         ],
         vec![String::from("4"), String::from("3"), String::from("2")],
     ];
-    let outputs = &[String::from("a"), String::from("6")];
+    let outputs = vec![String::from("a"), String::from("6")];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("1"), 2),
@@ -37,11 +47,14 @@ fn integration_test() {
         (String::from("0"), 2),
         (String::from("a"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
     let ssa_path = vec![(0, 1), (6, 2), (7, 3), (8, 4), (9, 5)];
 
     let contraction_path =
-        cotengra_optimize_from_path(&inputs, outputs, &size_dict, ssa_path, 8, true).unwrap();
+        cotengra_optimize_from_path(&network, ssa_path, &ReconfOptions::new(), true, true, None)
+            .unwrap()
+            .ssa_path;
     assert_eq!(
         contraction_path,
         vec![(4, 5), (1, 6), (3, 7), (0, 2), (8, 9)]
@@ -50,7 +63,7 @@ fn integration_test() {
 
 #[test]
 fn optimized_greedy_integration_test() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("0")],
         vec![String::from("51")],
         vec![String::from("0"), String::from("2")],
@@ -63,7 +76,7 @@ fn optimized_greedy_integration_test() {
         vec![String::from("3")],
         vec![String::from("4")],
     ];
-    let outputs = &[];
+    let outputs = vec![];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("51"), 2),
@@ -72,17 +85,52 @@ fn optimized_greedy_integration_test() {
         (String::from("4"), 2),
         (String::from("0"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
-    let contraction_path = cotengra_optimized_greedy(&inputs, outputs, &size_dict, 8).unwrap();
+    let contraction_path = cotengra_optimized_greedy(&network, &ReconfOptions::new(), true, None)
+        .unwrap()
+        .ssa_path;
     assert_eq!(
         contraction_path,
         vec![(0, 2), (3, 6), (4, 7), (5, 8), (1, 9)]
     );
 }
 
+#[test]
+fn optimizer_trait_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[
+            vec![0],
+            vec![51],
+            vec![0, 2],
+            vec![2, 51, 3, 4],
+            vec![3],
+            vec![4],
+        ],
+        &[],
+        &FxHashMap::from_iter([(51, 2), (2, 2), (3, 2), (4, 2), (0, 2)]),
+    )
+    .unwrap();
+
+    let optimizers: Vec<Box<dyn Optimizer>> = vec![
+        Box::new(CotengraGreedy {
+            options: ReconfOptions::new().with_subtree_size(8),
+            validate: true,
+        }),
+        Box::new(CotengraAnnealing {
+            options: AnnealingOptions::new().with_seed(4),
+            validate: true,
+            ..Default::default()
+        }),
+    ];
+    for optimizer in optimizers {
+        optimizer.optimize(&network).unwrap();
+    }
+}
+
 #[test]
 fn sa_integration_test() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("0")],
         vec![String::from("51")],
         vec![String::from("0"), String::from("2")],
@@ -95,7 +143,7 @@ fn sa_integration_test() {
         vec![String::from("3")],
         vec![String::from("4")],
     ];
-    let outputs = &[];
+    let outputs = vec![];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("51"), 2),
@@ -104,9 +152,17 @@ fn sa_integration_test() {
         (String::from("4"), 2),
         (String::from("0"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
-    let contraction_path =
-        cotengra_sa_tree(&inputs, outputs, None, None, &size_dict, Some(4)).unwrap();
+    let contraction_path = cotengra_sa_tree(
+        &network,
+        &InitialTree::Greedy,
+        &AnnealingOptions::new().with_seed(4),
+        true,
+        None,
+    )
+    .unwrap()
+    .ssa_path;
 
     assert_eq!(
         contraction_path,
@@ -116,7 +172,7 @@ fn sa_integration_test() {
 
 #[test]
 fn tempering_integration_test() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("0")],
         vec![String::from("51")],
         vec![String::from("0"), String::from("2")],
@@ -129,7 +185,7 @@ fn tempering_integration_test() {
         vec![String::from("3")],
         vec![String::from("4")],
     ];
-    let outputs = &[];
+    let outputs = vec![];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("51"), 2),
@@ -138,9 +194,17 @@ fn tempering_integration_test() {
         (String::from("4"), 2),
         (String::from("0"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
-    let contraction_path =
-        cotengra_tree_tempering(&inputs, outputs, None, &size_dict, Some(4)).unwrap();
+    let contraction_path = cotengra_tree_tempering(
+        &network,
+        &InitialTree::Greedy,
+        &TemperingOptions::new().with_seed(4),
+        true,
+        None,
+    )
+    .unwrap()
+    .ssa_path;
 
     assert_eq!(
         contraction_path,
@@ -150,7 +214,7 @@ fn tempering_integration_test() {
 
 #[test]
 fn test_hyper() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("0")],
         vec![String::from("51")],
         vec![String::from("0"), String::from("2")],
@@ -163,7 +227,7 @@ fn test_hyper() {
         vec![String::from("3")],
         vec![String::from("4")],
     ];
-    let outputs = &[];
+    let outputs = vec![];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("51"), 2),
@@ -172,19 +236,23 @@ fn test_hyper() {
         (String::from("4"), 2),
         (String::from("0"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
     let duration = Duration::from_secs(15);
     let contraction_path = cotengra_hyperoptimizer(
-        &inputs,
-        outputs,
-        &size_dict,
-        "kahypar",
-        &HyperOptions::default().with_max_time(&duration),
+        &network,
+        &[HyperMethod::Kahypar],
+        &HyperOptions::default().with_max_time(duration),
+        true,
+        None,
     )
-    .unwrap();
+    .unwrap()
+    .result
+    .ssa_path;
 
+    validate_ssa_path(&contraction_path, &network).unwrap();
     assert_eq!(
-        contraction_path,
+        ssa_to_replace_path(contraction_path, network.num_tensors()),
         vec![(1, 3), (4, 1), (5, 4), (0, 2), (5, 0)]
     );
 }
@@ -206,7 +274,7 @@ fn validate_path(path: &[(usize, usize)]) {
 /// Thus, we only check for validity of the returned path.
 #[test]
 fn test_stress_hyper() {
-    let inputs = [
+    let inputs = vec![
         vec![String::from("0")],
         vec![String::from("1")],
         vec![String::from("2")],
@@ -238,7 +306,7 @@ fn test_stress_hyper() {
         vec![String::from("19")],
         vec![String::from("15")],
     ];
-    let outputs = &[];
+    let outputs = vec![];
 
     let size_dict = FxHashMap::from_iter([
         (String::from("0"), 2),
@@ -262,16 +330,181 @@ fn test_stress_hyper() {
         (String::from("18"), 2),
         (String::from("19"), 2),
     ]);
+    let network = TensorNetwork::new(inputs, outputs, size_dict).unwrap();
 
     let duration = Duration::from_secs(15);
     let contraction_path = cotengra_hyperoptimizer(
-        &inputs,
-        outputs,
-        &size_dict,
-        "kahypar",
-        &HyperOptions::default().with_max_time(&duration),
+        &network,
+        &[HyperMethod::Kahypar],
+        &HyperOptions::default().with_max_time(duration),
+        true,
+        None,
+    )
+    .unwrap()
+    .result
+    .ssa_path;
+
+    validate_ssa_path(&contraction_path, &network).unwrap();
+    validate_path(&ssa_to_replace_path(
+        contraction_path,
+        network.num_tensors(),
+    ));
+}
+
+#[test]
+fn slicing_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 8), (1, 8), (2, 8), (3, 8)]),
     )
     .unwrap();
+    let ssa_path = vec![(0, 1), (2, 3), (4, 5)];
+
+    let options = SlicingOptions::new().with_target_size(8).with_seed(4);
+    let sliced = cotengra_slice(&network, &ssa_path, &options).unwrap();
+
+    assert!(!sliced.sliced_legs.is_empty());
+    assert!(sliced.slice_cost.max_size <= 8.0);
+    assert_eq!(
+        sliced.total_cost.flops,
+        sliced.num_slices as f64 * sliced.slice_cost.flops
+    );
 
-    validate_path(&contraction_path);
+    let tree = ContractionTree::from_ssa_path(&network, &ssa_path).unwrap();
+    assert_eq!(cotengra_slice_tree(&tree, &options).unwrap(), sliced);
+}
+
+#[test]
+fn slice_and_reconfigure_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 8), (1, 8), (2, 8), (3, 8), (4, 8)]),
+    )
+    .unwrap();
+    let ssa_path = vec![(0, 1), (5, 2), (6, 3), (7, 4)];
+
+    let sliced = cotengra_slice_and_reconfigure(
+        &network,
+        ssa_path,
+        &SliceReconfOptions::new(8).with_reconf_opts(ReconfOptions::new().with_subtree_size(4)),
+        true,
+        true,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(sliced.result.ssa_path.len(), 4);
+    assert!(sliced.result.max_size.is_finite());
+    assert!(!sliced.sliced.sliced_legs.is_empty());
+    assert!(sliced.sliced.slice_cost.max_size <= 8.0);
+}
+
+#[test]
+fn forest_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+    )
+    .unwrap();
+    let ssa_path = vec![(0, 1), (5, 2), (6, 3), (7, 4)];
+
+    let result = cotengra_reconfigure_forest(
+        &network,
+        ssa_path,
+        &ForestOptions::new()
+            .with_num_trees(2)
+            .with_num_restarts(2)
+            .with_parallel(false)
+            .with_seed(4),
+        true,
+        true,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(result.ssa_path.len(), 4);
+    assert_eq!(result.seed, Some(4));
+}
+
+#[test]
+fn initial_tree_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+    )
+    .unwrap();
+
+    for initial in [
+        InitialTree::RandomGreedy,
+        InitialTree::SsaPath(vec![(0, 1), (5, 2), (6, 3), (7, 4)]),
+        InitialTree::ReplacePath(vec![(0, 1), (0, 2), (0, 3), (0, 4)]),
+    ] {
+        let result = cotengra_sa_tree(
+            &network,
+            &initial,
+            &AnnealingOptions::new().with_seed(4),
+            true,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.ssa_path.len(), 4);
+    }
+}
+
+#[test]
+fn reusable_hyper_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5)]),
+    )
+    .unwrap();
+    let directory = std::env::temp_dir().join(format!("rustengra-cache-{}", std::process::id()));
+    let options = HyperOptions::new()
+        .with_max_repeats(4)
+        .with_directory(&directory);
+
+    let first =
+        cotengra_hyperoptimizer(&network, &[HyperMethod::Greedy], &options, true, None).unwrap();
+    let second =
+        cotengra_hyperoptimizer(&network, &[HyperMethod::Greedy], &options, true, None).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(first.trials.is_empty());
+    assert_eq!(first.result.ssa_path, second.result.ssa_path);
+}
+
+#[test]
+fn cache_integration_test() {
+    let network = TensorNetwork::from_usize_legs(
+        &[vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 0]],
+        &[],
+        &FxHashMap::from_iter([(0, 2), (1, 3), (2, 4), (3, 5), (4, 6)]),
+    )
+    .unwrap();
+    let directory =
+        std::env::temp_dir().join(format!("rustengra-cache-test-{}", std::process::id()));
+    let cache = PathCache::new(&directory);
+
+    let first =
+        cotengra_optimized_greedy(&network, &ReconfOptions::new(), true, Some(&cache)).unwrap();
+    let cached = cache.get(&network).unwrap().unwrap();
+    assert_eq!(cached.ssa_path, first.ssa_path);
+
+    // A worse starting path without reconfiguration yields the cached path
+    let result = cotengra_optimize_from_path(
+        &network,
+        vec![(0, 2), (5, 1), (6, 3), (7, 4)],
+        &ReconfOptions::new().with_maxiter(0),
+        true,
+        true,
+        Some(&cache),
+    )
+    .unwrap();
+    assert!(result.log10_flops <= first.log10_flops);
+    std::fs::remove_dir_all(&directory).unwrap();
 }